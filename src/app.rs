@@ -0,0 +1,2205 @@
+use crate::{cgroup, explain, live, net, pager, proc_events, prometheus, term, tree, users};
+use crate::repl::{Input, Prompt};
+use crate::delta::{
+    cpu_percent_between, ctxsw_rate_between, grows_steadily, memory_growth, pid_churn,
+    process_changes, rate_per_second, ProcessChange,
+};
+use crate::filter::{matches_name, owned_by, search, UsageThreshold};
+use crate::format::{
+    bar_segments, csv_field, format_kb, process_csv_row, process_json, process_header, process_header_both, process_row,
+    process_row_both, processes_json, sparkline, started_at, summary_line, timestamp_now, with_percent, with_thousands,
+    OutputFormat, PROCESS_CSV_HEADER,
+};
+use crate::output::{buffering, set_buffering, Buffering, RecordWriter};
+use crate::group::{
+    age_distribution, cpu_shares, group_by_name, group_by_user, nice_distribution, same_name, spread,
+    swapped_processes,
+};
+use crate::proc_events::PidTracker;
+use crate::signals::parse_signal;
+use crate::proc_reader::{
+    get_detailed_info, get_namespaced_pids, get_parent, get_process_metrics, get_smaps_rollup, get_socket_inodes,
+    get_thread_count, get_thread_metrics, io_accounting, get_uid, get_uids, get_wchan, list_pids, list_tids, peek_state, refresh_process_metrics, scan_processes, scan_processes_checked, ProcError, ProcessMetrics,
+};
+use crate::sort::{sort_processes, top_consumer, Resource, SortKey, Weights};
+use crate::system::{read_meminfo, read_pid_max, read_task_count, KERNEL_MEMORY_FIELDS};
+use crate::term::Color;
+use crate::parser::{Command, CommandParser};
+
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+
+use sysinfo::CpuExt;
+
+use sysinfo::{System, SystemExt};
+use std::time;
+
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, Write};
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+
+// Total and used memory in MB
+fn get_memory_stats() -> io::Result<(u64, u64)> {
+    let info = read_meminfo()?;
+    // Values are in KB. Divide by 1024 for MB.
+    Ok((info.kb("MemTotal") / 1024, info.used_kb() / 1024))
+}
+
+// Cumulative (read, write) bytes recorded per PID by `resetio`
+type IoBaselines = HashMap<u32, (u64, u64)>;
+
+// I/O since the recorded baseline, if `resetio` was used for this PID
+fn io_since_reset(metrics: &ProcessMetrics, baselines: &IoBaselines) -> Option<(u64, u64)> {
+    baselines.get(&metrics.pid).map(|&(read, write)| {
+        (
+            metrics.io_read_bytes.saturating_sub(read),
+            metrics.io_write_bytes.saturating_sub(write),
+        )
+    })
+}
+
+// Settings for `monitor`, straight from its command-line flags
+struct MonitorOptions {
+    interval: u64,
+    both: bool,
+    count: Option<u64>,
+    no_clear: bool,
+    sort_by: Option<String>,
+    utc: bool,
+    events: bool,
+    only_changed: bool,
+    adaptive: Option<(u64, u64)>,
+    threshold: UsageThreshold,
+    top: Option<usize>,
+    format: OutputFormat,
+}
+
+// Red for a process whose usage went up, green for down, yellow for a state change
+fn change_color(change: &ProcessChange) -> Option<Color> {
+    match change.direction() {
+        cmp::Ordering::Greater => Some(Color::Red),
+        cmp::Ordering::Less => Some(Color::Green),
+        cmp::Ordering::Equal if change.state => Some(Color::Yellow),
+        cmp::Ordering::Equal => None,
+    }
+}
+
+fn monitor_processes(options: MonitorOptions, io_baselines: &IoBaselines) {
+    let MonitorOptions {
+        interval,
+        both,
+        count,
+        no_clear,
+        sort_by,
+        utc,
+        events,
+        only_changed,
+        adaptive,
+        threshold,
+        top,
+        format,
+    } = options;
+    let color = term::color_enabled();
+    let mut sort_key = sort_by.as_deref().and_then(SortKey::parse);
+    if let (None, Some(key)) = (sort_key, sort_by.as_deref()) {
+        println!("Warning: unknown sort key '{}', showing processes by PID", key);
+    }
+    // The first N by PID wouldn't say much, so --top alone ranks by CPU like top(1)
+    if top.is_some() && sort_by.is_none() {
+        sort_key = Some(SortKey::Usage(Resource::Cpu));
+    }
+    let show_ctxsw = sort_key == Some(SortKey::CtxSw);
+    // refresh_process_metrics keeps I/O and context switch counts from the last
+    // full read, so when they're ranked on or printed as records, every process
+    // is read in full each refresh
+    let full_reads = show_ctxsw || sort_key == Some(SortKey::Usage(Resource::Io)) || format != OutputFormat::Table;
+
+    // PIDs we've already warned about, so a denied process doesn't nag every refresh
+    let mut warned = HashSet::new();
+    // The previous refresh, to turn cumulative context switch counts into rates
+    // and to find what changed for --only-changed
+    let mut previous: Vec<ProcessMetrics> = Vec::new();
+    let mut previous_at = time::Instant::now();
+
+    let event_source = if events {
+        match proc_events::subscribe() {
+            Ok(source) => Some(source),
+            Err(e) => {
+                println!("Process events unavailable ({}), rescanning /proc every refresh", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let mut tracker = PidTracker::new(event_source);
+
+    // CPU usage is measured between calls, so this first refresh sets the baseline
+    let mut sys = System::new();
+    sys.refresh_cpu();
+    let mut next_interval = || match adaptive {
+        Some((min, max)) => {
+            sys.refresh_cpu();
+            live::adaptive_interval(sys.global_cpu_info().cpu_usage(), min, max)
+        }
+        None => time::Duration::from_secs(interval),
+    };
+
+    // The last refresh's rows, each with the name the `/` filter matches against
+    // (warnings have none and always show), so a keystroke can redraw them
+    let mut header = if both { process_header_both() } else { process_header() };
+    if show_ctxsw {
+        header = format!("{} {:>10} {:>10}", header, "VolCS/s", "InvolCS/s");
+    }
+    let rows: RefCell<Vec<(Option<String>, String)>> = RefCell::new(Vec::new());
+    let filter = RefCell::new(live::LiveFilter::default());
+    let draw = || {
+        if no_clear {
+            // Keep earlier refreshes in scrollback, marked so they can be told apart
+            println!("--- {} ---", timestamp_now(utc, "%H:%M:%S"));
+        } else {
+            // Clear screen (optional for nice display)
+            print!("\x1B[2J\x1B[H");
+        }
+        println!("{}", header);
+        let filter = filter.borrow();
+        // --top counts the processes left after the `/` filter
+        let mut shown = 0;
+        for (comm, row) in rows.borrow().iter() {
+            match comm {
+                None => println!("{}", row),
+                Some(comm) if filter.allows(comm) && top.is_none_or(|top| shown < top) => {
+                    shown += 1;
+                    println!("{}", row);
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(status) = filter.status() {
+            println!("{}", status);
+        }
+    };
+
+    // JSON and CSV records, flushed as the `buffer` mode says
+    let mut records_out = RecordWriter::stdout();
+    let refresh = || {
+        let mut rows = rows.borrow_mut();
+        rows.clear();
+
+        let mut processes = Vec::new();
+        let update = tracker.pids();
+        let known: HashMap<u32, &ProcessMetrics> = previous.iter().map(|m| (m.pid, m)).collect();
+        for &pid in &update.pids {
+            // Between full rescans, a process the events say is unchanged only
+            // needs its stat re-read, which never blocks. One with a resetio
+            // baseline shows I/O since the reset, so it's always read in full.
+            let cached = known
+                .get(&pid)
+                .filter(|_| !full_reads && !io_baselines.contains_key(&pid) && !update.needs_full_read(pid));
+            // A process in uninterruptible sleep (e.g. on a hung NFS mount) can block
+            // reads of its other /proc files and freeze the whole refresh, so skip it
+            if cached.is_none()
+                && let Ok((comm, 'D')) = peek_state(pid)
+            {
+                let row = format!("{:<8} {:<15} [skipped: uninterruptible sleep (D)]", pid, comm);
+                rows.push((Some(comm), row));
+                continue;
+            }
+            let metrics = match cached {
+                Some(old) => refresh_process_metrics(old),
+                None => get_process_metrics(pid),
+            };
+            match metrics {
+                Ok(metrics) => processes.push(metrics),
+                // The process exited between listing /proc and reading it
+                Err(ProcError::NotFound) => {}
+                Err(e) => {
+                    if warned.insert(pid) {
+                        rows.push((None, format!("{:<8} warning: {}", pid, e)));
+                    }
+                }
+            }
+        }
+
+        let elapsed = previous_at.elapsed().as_secs_f64();
+        // Show CPU% over the last interval, like top, rather than the lifetime
+        // average from stat, which barely moves for a long-running process
+        let cpu = cpu_percent_between(&previous, &processes, elapsed);
+        for metrics in processes.iter_mut() {
+            metrics.cpu_time = cpu[&metrics.pid];
+        }
+        let rates = ctxsw_rate_between(&previous, &processes, elapsed);
+        match sort_key {
+            // The first refresh has no baseline, so every rate is 0 until the next one
+            Some(SortKey::CtxSw) => processes.sort_by(|a, b| {
+                rates[&b.pid].total().total_cmp(&rates[&a.pid].total()).then_with(|| a.pid.cmp(&b.pid))
+            }),
+            Some(key) => sort_processes(&mut processes, key),
+            None => {}
+        }
+
+        // The first refresh has nothing to compare against, so it shows everything
+        let changes = process_changes(&previous, &processes);
+        let first_refresh = previous.is_empty();
+        // What JSON and CSV print this refresh, in place of the table rows
+        let mut records = Vec::new();
+
+        for metrics in &processes {
+            let change = changes.get(&metrics.pid);
+            // A process without an entry is new since the last refresh, which counts as a change
+            if only_changed && !first_refresh && change.is_some_and(|change| !change.changed()) {
+                continue;
+            }
+            // Filtered here rather than out of `processes`, which must keep every
+            // process as the baseline for the next refresh's CPU%
+            if !threshold.allows(metrics) {
+                continue;
+            }
+            if format != OutputFormat::Table {
+                records.push(metrics.clone());
+                continue;
+            }
+
+            // Print formatted process info
+            let mut row = if both {
+                process_row_both(metrics)
+            } else {
+                process_row(metrics)
+            };
+            if show_ctxsw {
+                let rate = rates[&metrics.pid];
+                row = format!("{} {:>10.1} {:>10.1}", row, rate.voluntary, rate.nonvoluntary);
+            }
+            if only_changed
+                && color
+                && let Some(tint) = change.and_then(change_color)
+            {
+                row = term::paint(&row, tint);
+            }
+            if let Some((read, write)) = io_since_reset(metrics, io_baselines) {
+                row = format!("{} (+{}/+{} since reset)", row, read, write);
+            }
+            rows.push((Some(metrics.comm.clone()), row));
+        }
+        drop(rows);
+        records.truncate(top.unwrap_or(records.len()));
+        let written = match format {
+            // One line per refresh when compact, so a consumer can read record by record
+            OutputFormat::Json { pretty } => records_out.record(&processes_json(&records, pretty)),
+            OutputFormat::Csv => {
+                let header = if first_refresh { records_out.record(PROCESS_CSV_HEADER) } else { Ok(()) };
+                header.and_then(|_| {
+                    records
+                        .iter()
+                        .try_for_each(|metrics| records_out.record(&process_csv_row(metrics)))
+                })
+            }
+            OutputFormat::Table => {
+                draw();
+                Ok(())
+            }
+        };
+        // Nobody is reading any more (a closed pipe), so stop refreshing
+        if written.is_err() {
+            return false;
+        }
+
+        previous = processes;
+        previous_at = time::Instant::now();
+        true
+    };
+
+    // Between refreshes, keys typed after `/` narrow the rows on screen at once
+    let wait = || {
+        let _ = io::stdout().flush();
+        let deadline = time::Instant::now() + next_interval();
+        loop {
+            let typing = filter.borrow().typing;
+            match live::wait_for_key(deadline, typing) {
+                live::Wait::Elapsed => return false,
+                live::Wait::Quit => return true,
+                live::Wait::Key(code) => {
+                    // Records already printed can't be narrowed, so only the table filters
+                    if format == OutputFormat::Table && filter.borrow_mut().handle(code) {
+                        draw();
+                        let _ = io::stdout().flush();
+                    }
+                }
+            }
+        }
+    };
+
+    live::run_refresh_loop_with(count, refresh, wait);
+}
+
+// The signal a kill command asked for, SIGTERM when none was given. An
+// unrecognized one is reported and nothing should be sent.
+fn requested_signal(name: Option<&str>) -> Option<Signal> {
+    let Some(name) = name else {
+        return Some(Signal::SIGTERM);
+    };
+    let sig = parse_signal(name);
+    if sig.is_none() {
+        println!("Unknown signal '{}' (expected a name such as SIGHUP or HUP, or a number)", name);
+    }
+    sig
+}
+
+// Signal every process owned by a user. Nothing is sent until the user confirms
+// with -y, and root's or our own processes also take --force.
+fn kill_user(user: &str, sig: Signal, confirmed: bool, force: bool, rate: Option<u32>) {
+    let Some(uid) = users::resolve_uid(user) else {
+        println!("Unknown user '{}'", user);
+        return;
+    };
+    if (uid == 0 || uid == unsafe { libc::getuid() }) && !force {
+        println!("Refusing to signal all processes of {} (UID {}) without --force", user, uid);
+        return;
+    }
+
+    // Never signal ourselves, so every result can still be reported
+    let own_pid = std::process::id();
+    let targets: Vec<u32> = list_pids()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|&pid| pid != own_pid && matches!(get_uid(pid), Ok(Some(owner)) if owner == uid))
+        .collect();
+    if targets.is_empty() {
+        println!("No processes owned by {}", user);
+        return;
+    }
+
+    if !confirmed {
+        println!("Would send {} to {} processes owned by {}:", sig, targets.len(), user);
+        for pid in &targets {
+            let comm = peek_state(*pid).map(|(comm, _)| comm).unwrap_or_default();
+            println!("  {:<8} {}", pid, comm);
+        }
+        println!("Re-run with -y to confirm");
+        return;
+    }
+
+    signal_all(&targets, sig, rate);
+}
+
+// Send `sig` to each target, at most `rate` per second, then say how many got
+// it. Every bulk kill goes through here so --rate applies to all of them.
+fn signal_all(targets: &[u32], sig: Signal, rate: Option<u32>) {
+    // A slow --rate over many processes takes a while, so Ctrl-C stops early
+    let _interrupts = live::catch_interrupts();
+    let mut pacer = live::Pacer::new(rate);
+    let mut signalled = 0;
+    for (sent, pid) in targets.iter().copied().enumerate() {
+        pacer.wait();
+        if live::interrupted() {
+            println!("Interrupted after {} of {} processes", sent, targets.len());
+            break;
+        }
+        match signal::kill(Pid::from_raw(pid as i32), sig) {
+            Ok(_) => {
+                signalled += 1;
+                println!("PID {}: sent {}", pid, sig);
+            }
+            // It may have exited since the scan, which is fine
+            Err(e) => println!("PID {}: failed: {}", pid, e),
+        }
+    }
+    println!("Signalled {} of {} processes", signalled, targets.len());
+}
+
+// Signal a process and everything below it. The whole tree is listed before the
+// first signal, so children re-parented on their parent's exit still get theirs.
+fn kill_tree(pid: u32, sig: Signal, rate: Option<u32>) {
+    if pid == 1 {
+        println!("Refusing to signal PID 1 and every process under it");
+        return;
+    }
+    let processes = scan_processes();
+    if !processes.iter().any(|metrics| metrics.pid == pid) {
+        println!("No such process: {}", pid);
+        return;
+    }
+    // Never signal ourselves, so every result can still be reported
+    let own_pid = std::process::id();
+    let targets: Vec<u32> = tree::descendants(&tree::children_map(&processes), pid)
+        .into_iter()
+        .filter(|&target| target != own_pid)
+        .collect();
+    signal_all(&targets, sig, rate);
+}
+
+// Signal processes by name. One match is signalled right away; several are
+// listed instead unless --all says they're all meant.
+fn kill_by_name(name: &str, sig: Signal, all: bool, rate: Option<u32>) {
+    let own_pid = std::process::id();
+    let matches: Vec<(u32, String)> = list_pids()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|&pid| pid != own_pid)
+        .filter_map(|pid| peek_state(pid).ok().map(|(comm, _)| (pid, comm)))
+        .filter(|(_, comm)| matches_name(comm, name, true))
+        .collect();
+
+    match matches.as_slice() {
+        [] => println!("No process named '{}'", name),
+        [_, _, ..] if !all => {
+            println!("{} processes are named '{}':", matches.len(), name);
+            for (pid, comm) in &matches {
+                println!("  {:<8} {}", pid, comm);
+            }
+            println!("Re-run with --all to signal every one, or use kill PID");
+        }
+        _ => {
+            let targets: Vec<u32> = matches.iter().map(|&(pid, _)| pid).collect();
+            signal_all(&targets, sig, rate);
+        }
+    }
+}
+
+// How often `kill --escalate` checks whether the process has exited
+const ESCALATE_POLL: time::Duration = time::Duration::from_millis(100);
+
+// A process that's gone or only left as a zombie has finished exiting
+fn has_exited(pid: u32) -> bool {
+    !matches!(peek_state(pid), Ok((_, state)) if state != 'Z')
+}
+
+// Ask a process to terminate with SIGTERM and only force it with SIGKILL if
+// it's still around after the grace period, like a service manager would
+fn kill_with_escalation(pid: u32, grace: u64) {
+    let target = Pid::from_raw(pid as i32);
+    if let Err(e) = signal::kill(target, Signal::SIGTERM) {
+        println!("Failed to send SIGTERM to process {}: {}", pid, e);
+        return;
+    }
+    println!("Sent SIGTERM to process {}, waiting up to {}s for it to exit", pid, grace);
+
+    let deadline = time::Instant::now() + time::Duration::from_secs(grace);
+    loop {
+        if has_exited(pid) {
+            println!("Process {} exited after SIGTERM", pid);
+            return;
+        }
+        if time::Instant::now() >= deadline {
+            break;
+        }
+        std::thread::sleep(ESCALATE_POLL);
+    }
+
+    println!("Process {} still running after {}s, sending SIGKILL", pid, grace);
+    match signal::kill(target, Signal::SIGKILL) {
+        Ok(_) => println!("Sent SIGKILL to process {}", pid),
+        // It exited on its own between the last check and SIGKILL
+        Err(nix::errno::Errno::ESRCH) => println!("Process {} exited after SIGTERM", pid),
+        Err(e) => println!("Failed to send SIGKILL to process {}: {}", pid, e),
+    }
+}
+
+// Send a signal to one thread with tgkill, which fails instead of hitting an
+// unrelated thread if the TID was reused by another process
+fn send_to_thread(pid: u32, tid: u32, sig: Signal) -> io::Result<()> {
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_tgkill,
+            pid as libc::pid_t,
+            tid as libc::pid_t,
+            sig as libc::c_int,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+fn kill_thread(pid: u32, tid: u32, sig: Signal) {
+    match list_tids(pid) {
+        Ok(tids) if tids.contains(&tid) => match send_to_thread(pid, tid, sig) {
+            Ok(()) => println!("Sent {} to thread {} of process {}", sig, tid, pid),
+            Err(e) => println!("Failed to signal thread {} of process {}: {}", tid, pid, e),
+        },
+        Ok(_) => println!("Thread {} does not belong to process {}", tid, pid),
+        Err(e) => println!("Failed to list threads of process {}: {}", pid, e),
+    }
+}
+
+// Set a process's nice value with setpriority(2)
+fn renice_process(pid: u32, nice: i32) {
+    // SAFETY: setpriority only reads its arguments
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid, nice) } == 0 {
+        println!("Process {} now has nice value {}", pid, nice);
+        return;
+    }
+    let e = io::Error::last_os_error();
+    match e.raw_os_error() {
+        Some(libc::ESRCH) => println!("No process with PID {}", pid),
+        Some(libc::EACCES) => {
+            println!("Permission denied: lowering a nice value needs root (CAP_SYS_NICE)")
+        }
+        Some(libc::EPERM) => {
+            println!("Permission denied: process {} belongs to another user", pid)
+        }
+        _ => println!("Failed to renice process {}: {}", pid, e),
+    }
+}
+
+// Filters applied to the ps listing: name patterns, either of which may be a
+// glob, and the owner from `-u`
+struct ListFilters {
+    filter: Option<String>,
+    exclude: Option<String>,
+    user: Option<String>,
+}
+
+impl ListFilters {
+    fn allows(&self, comm: &str) -> bool {
+        self.filter.as_ref().is_none_or(|pattern| matches_name(comm, pattern, false))
+            && self.exclude.as_ref().is_none_or(|pattern| !matches_name(comm, pattern, false))
+    }
+
+    fn allows_owner(&self, metrics: &ProcessMetrics) -> bool {
+        self.user.as_ref().is_none_or(|user| owned_by(metrics, user))
+    }
+}
+
+// Processes whose name matches, in the same table as `ps`
+fn search_processes(name: &str, exact: bool, count: bool) {
+    // Processes that exit mid-scan are skipped by scan_processes
+    let processes = scan_processes();
+    let matches = search(&processes, name, exact);
+    if count {
+        println!("{}", matches.len());
+        return;
+    }
+    if matches.is_empty() {
+        println!("No matching processes for '{}'", name);
+        return;
+    }
+    println!("{}", process_header());
+    for metrics in matches {
+        println!("{}", process_row(metrics));
+    }
+}
+
+// One row per thread, like `ps -eLf`
+fn list_threads(use_pager: bool, filters: &ListFilters) {
+    let mut lines = vec![format!(
+        "{:<8} {:<8} {:<15} {:<15} State",
+        "PID", "TID", "Process", "Thread"
+    )];
+    for pid in list_pids().unwrap_or_default() {
+        let Ok((process_name, _)) = peek_state(pid) else {
+            continue;
+        };
+        if !filters.allows(&process_name) {
+            continue;
+        }
+        for tid in list_tids(pid).unwrap_or_default() {
+            // Threads can exit between listing the task directory and reading them
+            if let Ok(thread) = get_thread_metrics(pid, tid) {
+                lines.push(format!(
+                    "{:<8} {:<8} {:<15} {:<15} {}",
+                    thread.pid, thread.tid, process_name, thread.comm, thread.state
+                ));
+            }
+        }
+    }
+    pager::emit(&lines, use_pager);
+}
+
+// The threads of one process, from /proc/[pid]/task
+fn show_threads(pid: u32) {
+    let tids = match list_tids(pid) {
+        Ok(tids) => tids,
+        Err(ProcError::NotFound) => {
+            println!("No such process: {}", pid);
+            return;
+        }
+        Err(e) => {
+            println!("Failed to list threads of process {}: {}", pid, e);
+            return;
+        }
+    };
+    println!("{:<8} {:<15} State", "TID", "Thread");
+    for tid in tids {
+        // Threads can exit between listing the task directory and reading them
+        if let Ok(thread) = get_thread_metrics(pid, tid) {
+            println!("{:<8} {:<15} {}", thread.tid, thread.comm, thread.state);
+        }
+    }
+}
+
+// Settings for `ps` that shape its output, straight from its command-line flags
+struct ListOptions {
+    sort_by: Option<String>,
+    weights: Option<String>,
+    both: bool,
+    last_cpu: bool,
+    pager: bool,
+    format: OutputFormat,
+}
+
+fn list_processes(options: ListOptions, filters: ListFilters, threshold: UsageThreshold) {
+    let ListOptions { sort_by, weights, both, last_cpu, pager: use_pager, format } = options;
+    let mut sort_key = sort_by.as_deref().and_then(SortKey::parse);
+    if let (None, Some(key)) = (sort_key, sort_by.as_deref()) {
+        println!("Warning: unknown sort key '{}', showing processes by PID", key);
+    }
+    if let (Some(SortKey::Score(_)), Some(spec)) = (sort_key, weights.as_deref()) {
+        match Weights::parse(spec) {
+            Some(weights) => sort_key = Some(SortKey::Score(weights)),
+            None => println!("Warning: invalid --weights '{}', using cpu=0.5,mem=0.5", spec),
+        }
+    }
+
+    let (mut processes, failures) = scan_processes_checked();
+    processes.retain(|metrics| filters.allows(&metrics.comm) && filters.allows_owner(metrics));
+
+    // smaps_rollup is expensive to read, so PSS is only collected when asked for
+    let show_pss = sort_key == Some(SortKey::Pss);
+    let show_ctxsw = sort_key == Some(SortKey::CtxSw);
+    if show_pss {
+        for metrics in processes.iter_mut() {
+            metrics.pss = get_smaps_rollup(metrics.pid).ok().map(|smaps| smaps.pss);
+        }
+    }
+    processes.retain(|metrics| threshold.allows(metrics));
+
+    match sort_key {
+        Some(key) => sort_processes(&mut processes, key),
+        None => processes.sort_by_key(|metrics| metrics.pid),
+    }
+
+    // Scripts get every field, so the table's optional columns don't apply
+    match format {
+        OutputFormat::Json { pretty } => return pager::emit_records(&[processes_json(&processes, pretty)], use_pager),
+        OutputFormat::Csv => {
+            let mut lines = vec![PROCESS_CSV_HEADER.to_string()];
+            lines.extend(processes.iter().map(process_csv_row));
+            return pager::emit_records(&lines, use_pager);
+        }
+        OutputFormat::Table => {}
+    }
+
+    let (mut header, row): (String, fn(&ProcessMetrics) -> String) = if both {
+        (process_header_both(), process_row_both)
+    } else {
+        (process_header(), process_row)
+    };
+
+    let mut lines = Vec::with_capacity(processes.len() + 2);
+    if show_pss {
+        header = format!("{} {:>10}", header, "PSS");
+    }
+    if show_ctxsw {
+        header = format!("{} {:>10} {:>10}", header, "VolCS", "InvolCS");
+    }
+    if last_cpu {
+        header = format!("{} {:>5}", header, "CPU#");
+    }
+    lines.push(header);
+    for metrics in &processes {
+        let mut line = row(metrics);
+        if show_pss {
+            let pss = metrics.pss.map(format_kb).unwrap_or_else(|| "-".to_string());
+            line = format!("{} {:>10}", line, pss);
+        }
+        if show_ctxsw {
+            line = format!(
+                "{} {:>10} {:>10}",
+                line, metrics.voluntary_ctxt_switches, metrics.nonvoluntary_ctxt_switches
+            );
+        }
+        if last_cpu {
+            let cpu = metrics.last_cpu.map_or_else(|| "-".to_string(), |cpu| cpu.to_string());
+            line = format!("{} {:>5}", line, cpu);
+        }
+        lines.push(line);
+    }
+
+    if show_pss {
+        let unreadable = processes.iter().filter(|m| m.pss.is_none()).count();
+        if unreadable > 0 {
+            lines.push(format!(
+                "Note: PSS unavailable for {} processes (reading other users' smaps_rollup requires root)",
+                unreadable
+            ));
+        }
+    }
+    // Vanished processes are expected; anything else means a PID went missing
+    // from the listing for a reason worth knowing
+    for (pid, e) in &failures {
+        lines.push(format!("Warning: could not read process {}: {}", pid, e));
+    }
+
+    pager::emit(&lines, use_pager);
+}
+
+// Number of samples kept for the `stats --refresh` sparklines unless --history is given
+const DEFAULT_HISTORY: usize = 30;
+
+// Append a sample, dropping the oldest once the buffer holds `capacity` values
+fn push_sample(buffer: &mut VecDeque<f64>, value: f64, capacity: usize) {
+    buffer.push_back(value);
+    while buffer.len() > capacity.max(1) {
+        buffer.pop_front();
+    }
+}
+
+// Draw an htop-style meter: each (value, symbol, color) part gets a share of the
+// bar proportional to its value, and `rest` is left blank
+fn meter(label: &str, parts: &[(u64, char, Color)], rest: u64, summary: &str) -> String {
+    let width = term::terminal_width()
+        .saturating_sub(label.len() + " [] ".len() + summary.len())
+        .max(10);
+    let mut values: Vec<u64> = parts.iter().map(|&(value, _, _)| value).collect();
+    values.push(rest);
+    let cells = bar_segments(&values, width);
+
+    let color = term::color_enabled();
+    let mut bar = String::new();
+    for (&(_, symbol, tint), &count) in parts.iter().zip(&cells) {
+        if color {
+            bar.push_str(&term::paint(&"|".repeat(count), tint));
+        } else {
+            bar.push_str(&symbol.to_string().repeat(count));
+        }
+    }
+    bar.push_str(&" ".repeat(cells[parts.len()]));
+    format!("{} [{}] {}", label, bar, summary)
+}
+
+// Memory meter: used, buffers and cached memory, with free space blank
+fn memory_bar() -> Option<String> {
+    let info = read_meminfo().ok()?;
+    let total = info.kb("MemTotal");
+    let free = info.kb("MemFree");
+    let buffers = info.kb("Buffers");
+    let cached = info.kb("Cached");
+    let used = total.saturating_sub(free + buffers + cached);
+
+    let parts = [
+        (used, '#', Color::Green),
+        (buffers, '+', Color::Blue),
+        (cached, '~', Color::Yellow),
+    ];
+    let summary = format!("{}/{}", format_kb(used), format_kb(total));
+    Some(meter("Mem  ", &parts, free, &summary))
+}
+
+// Where kernel memory goes, for when process RSS doesn't add up to used memory
+fn show_kernel_memory() {
+    let info = match read_meminfo() {
+        Ok(info) => info,
+        Err(e) => {
+            println!("Error reading /proc/meminfo: {}", e);
+            return;
+        }
+    };
+    let total = info.kb("MemTotal");
+    let percent = |kb: u64| if total > 0 { kb as f64 / total as f64 * 100.0 } else { 0.0 };
+
+    println!("Kernel memory:");
+    for (key, label) in KERNEL_MEMORY_FIELDS {
+        let kb = info.kb(key);
+        println!("  {:<16} {}", format!("{}:", label), with_percent(&format_kb(kb), percent(kb)));
+    }
+    let kernel = info.kernel_kb();
+    println!("  {:<16} {}", "Total:", with_percent(&format_kb(kernel), percent(kernel)));
+}
+
+// `pidstat` warns once this much of the PID space is taken
+const PID_WARN_PERCENT: f64 = 80.0;
+
+// How close the system is to running out of PIDs, e.g. during a fork bomb
+fn show_pid_usage() {
+    let pid_max = match read_pid_max() {
+        Ok(pid_max) => pid_max,
+        Err(e) => {
+            println!("Error reading /proc/sys/kernel/pid_max: {}", e);
+            return;
+        }
+    };
+    let processes = list_pids().unwrap_or_default().len() as u64;
+    println!(
+        "{} / {} PIDs in use by processes ({:.2}%)",
+        with_thousands(processes),
+        with_thousands(pid_max),
+        percent_of(processes, pid_max)
+    );
+
+    // Every thread takes a PID too, so the task count is what actually runs out
+    let in_use = match read_task_count() {
+        Some(tasks) => {
+            println!(
+                "{} / {} including threads ({:.2}%)",
+                with_thousands(tasks),
+                with_thousands(pid_max),
+                percent_of(tasks, pid_max)
+            );
+            tasks
+        }
+        None => processes,
+    };
+    if percent_of(in_use, pid_max) >= PID_WARN_PERCENT {
+        println!(
+            "Warning: over {:.0}% of PIDs are in use; new processes will fail to start once they run out",
+            PID_WARN_PERCENT
+        );
+    }
+}
+
+// Processes running with another user's privileges: a setuid binary like
+// passwd or sudo (real user, effective root), or a daemon that dropped root
+// for its effective UID. Gaining root is the case worth auditing, so it's red.
+fn show_setuid_processes() {
+    let names = users::user_names();
+    let color = term::color_enabled();
+    let mut found = 0;
+    for pid in list_pids().unwrap_or_default() {
+        let Ok(Some((real, effective))) = get_uids(pid) else {
+            continue;
+        };
+        if real == effective {
+            continue;
+        }
+        if found == 0 {
+            println!("{:<8} {:<15} {:<12} Effective", "PID", "Process", "Real");
+        }
+        found += 1;
+        let comm = peek_state(pid).map(|(comm, _)| comm).unwrap_or_default();
+        let mut row = format!(
+            "{:<8} {:<15} {:<12} {}",
+            pid,
+            comm,
+            users::display_name(&names, real),
+            users::display_name(&names, effective)
+        );
+        if effective == 0 && color {
+            row = term::paint(&row, Color::Red);
+        }
+        println!("{}", row);
+    }
+    if found == 0 {
+        println!("No process has an effective UID different from its real UID");
+    }
+}
+
+// One meter per core when the terminal can show colors, a compact numeric line otherwise
+fn print_core_usage(sys: &System) {
+    if term::color_enabled() {
+        for (i, cpu) in sys.cpus().iter().enumerate() {
+            // Tenths of a percent keep the bar proportions precise
+            let usage = (cpu.cpu_usage().clamp(0.0, 100.0) * 10.0) as u64;
+            let label = format!("CPU{:<2}", i);
+            let summary = format!("{:5.1}%", cpu.cpu_usage());
+            println!("{}", meter(&label, &[(usage, '|', Color::Green)], 1000 - usage, &summary));
+        }
+    } else {
+        let cores: Vec<String> = sys
+            .cpus()
+            .iter()
+            .enumerate()
+            .map(|(i, cpu)| format!("CPU{} {:.1}%", i, cpu.cpu_usage()))
+            .collect();
+        println!("Per-core usage: {}", cores.join("  "));
+    }
+}
+
+// Percentage of `part` in `whole`, 0 when there's nothing to divide by
+fn percent_of(part: u64, whole: u64) -> f64 {
+    if whole > 0 {
+        part as f64 / whole as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+// One reading of the figures `stats --summary-only` prints and `statlog` records
+struct SystemSample {
+    cpu: f64,
+    mem_percent: f64,
+    swap_percent: f64,
+    load: [f64; 3],
+}
+
+fn system_sample(sys: &System) -> SystemSample {
+    let (total_mb, used_mb) = get_memory_stats().unwrap_or((0, 0));
+    let swap_percent = read_meminfo()
+        .map(|info| {
+            let total = info.kb("SwapTotal");
+            percent_of(total.saturating_sub(info.kb("SwapFree")), total)
+        })
+        .unwrap_or(0.0);
+    let load = sys.load_average();
+    SystemSample {
+        cpu: sys.global_cpu_info().cpu_usage() as f64,
+        mem_percent: percent_of(used_mb, total_mb),
+        swap_percent,
+        load: [load.one, load.five, load.fifteen],
+    }
+}
+
+// `stats --summary-only`: CPU, memory, swap and load on a single line
+fn system_summary(sys: &System) -> String {
+    let sample = system_sample(sys);
+    summary_line(sample.cpu, sample.mem_percent, sample.swap_percent, sample.load)
+}
+
+const STATLOG_HEADER: &str = "timestamp,cpu_percent,mem_percent,swap_percent,load1,load5,load15";
+
+// `stats --refresh` for a log file: append one CSV row of system-wide figures
+// per interval until the user presses `q`
+fn log_system_stats(path: &str, interval: u64, utc: bool) {
+    let Some(mut file) = open_csv(path, STATLOG_HEADER) else {
+        return;
+    };
+
+    println!("Logging system stats to {} every {}s (press 'q' to stop)", path, interval);
+    let _interrupts = live::catch_interrupts();
+    let mut sys = System::new_all();
+    // CPU usage is measured between two refreshes, so the first row needs a baseline
+    std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+    let mut rows = 0;
+    loop {
+        sys.refresh_cpu();
+        let sample = system_sample(&sys);
+        let row = format!(
+            "{},{:.1},{:.1},{:.1},{:.2},{:.2},{:.2}",
+            timestamp_now(utc, "%Y-%m-%d %H:%M:%S"),
+            sample.cpu,
+            sample.mem_percent,
+            sample.swap_percent,
+            sample.load[0],
+            sample.load[1],
+            sample.load[2]
+        );
+        if let Err(e) = writeln!(file, "{}", row).and_then(|_| file.flush()) {
+            println!("Failed to write to {}: {}", path, e);
+            break;
+        }
+        rows += 1;
+
+        if live::wait_for_quit(time::Duration::from_secs(interval)) {
+            break;
+        }
+    }
+    println!("Logged {} samples to {}", rows, path);
+}
+
+fn print_memory_stats(memory: &io::Result<(u64, u64)>) {
+    match memory {
+        Ok((total_mb, used_mb)) => {
+            println!("Total memory: {} MB", total_mb);
+            println!("Used memory: {} MB", used_mb);
+        }
+        Err(e) => println!("Error reading /proc/meminfo: {}", e),
+    }
+}
+
+fn show_system_stats(
+    interval: u64,
+    history: usize,
+    show_bar: bool,
+    count: Option<u64>,
+    summary_only: bool,
+) {
+    let mut sys = System::new_all();
+    if summary_only {
+        // No clearing or meters, so each refresh appends one line a status bar can read
+        if interval == 0 {
+            sys.refresh_all();
+            std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+            sys.refresh_cpu();
+            println!("{}", system_summary(&sys));
+            return;
+        }
+        live::run_refresh_loop(time::Duration::from_secs(interval), count, || {
+            sys.refresh_all();
+            println!("{}", system_summary(&sys));
+            std::io::stdout().flush().unwrap();
+            true
+        });
+    } else if interval > 0 {
+        let mut cpu_history = VecDeque::with_capacity(history);
+        let mut mem_history = VecDeque::with_capacity(history);
+        live::run_refresh_loop(time::Duration::from_secs(interval), count, || {
+            sys.refresh_all();
+            let memory = get_memory_stats();
+            let cpu = sys.global_cpu_info().cpu_usage() as f64;
+            let mem_percent = match memory {
+                Ok((total_mb, used_mb)) => percent_of(used_mb, total_mb),
+                Err(_) => 0.0,
+            };
+            push_sample(&mut cpu_history, cpu, history);
+            push_sample(&mut mem_history, mem_percent, history);
+
+            print!("\x1B[2J\x1B[H"); // Clear screen
+            print_memory_stats(&memory);
+            if show_bar && let Some(bar) = memory_bar() {
+                println!("{}", bar);
+            }
+            println!("CPU usage: {:.2}%", cpu);
+            if show_bar {
+                print_core_usage(&sys);
+            }
+            println!("CPU history:    {}", sparkline(&cpu_history, 100.0));
+            println!("Memory history: {}", sparkline(&mem_history, 100.0));
+            std::io::stdout().flush().unwrap();
+            true
+        });
+    } else {
+        // CPU usage is measured between two refreshes, so take a second sample
+        sys.refresh_all();
+        std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+        sys.refresh_cpu();
+        print_memory_stats(&get_memory_stats());
+        if show_bar && let Some(bar) = memory_bar() {
+            println!("{}", bar);
+        }
+        println!("CPU usage: {:.2}%", sys.global_cpu_info().cpu_usage());
+        if show_bar {
+            print_core_usage(&sys);
+        }
+    }
+}
+
+fn show_memory_growth(interval: u64, top: usize) {
+    println!("Sampling memory for {} seconds...", interval);
+    let before = scan_processes();
+    let interrupts = live::catch_interrupts();
+    if live::wait_for_quit(time::Duration::from_secs(interval)) {
+        println!("Cancelled");
+        return;
+    }
+    drop(interrupts);
+    let after = scan_processes();
+
+    let report = memory_growth(&before, &after);
+    if report.grown.is_empty() {
+        println!("No process grew in memory over {} seconds", interval);
+    } else {
+        println!(
+            "{:<8} {:<15} {:<12} {:<12} {:<12} Growth",
+            "PID", "Process", "Before(KB)", "After(KB)", "Delta(KB)"
+        );
+        for growth in report.grown.iter().take(top) {
+            println!(
+                "{:<8} {:<15} {:<12} {:<12} {:<12} {:.1}%",
+                growth.pid,
+                growth.comm,
+                growth.before_kb,
+                growth.after_kb,
+                format!("+{}", growth.delta_kb()),
+                growth.percent()
+            );
+        }
+    }
+    println!(
+        "{} processes started and {} exited during the interval",
+        report.started, report.exited
+    );
+}
+
+const RECORD_HEADER: &str = "timestamp,pid,comm,user,cpu_percent,mem_kb,io_read_bytes,io_write_bytes";
+
+// Open a CSV file for appending. Only a new (empty) file gets the header, so
+// appending to an old recording stays valid CSV.
+fn open_csv(path: &str, header: &str) -> Option<fs::File> {
+    let mut file = match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("Failed to open {}: {}", path, e);
+            return None;
+        }
+    };
+
+    let is_new = file.metadata().map(|m| m.len() == 0).unwrap_or(false);
+    if is_new && let Err(e) = writeln!(file, "{}", header) {
+        println!("Failed to write to {}: {}", path, e);
+        return None;
+    }
+    Some(file)
+}
+
+// Append one CSV row per interval until the process exits or the user presses `q`
+fn record_process(pid: u32, path: &str, interval: u64, utc: bool) {
+    let Some(mut file) = open_csv(path, RECORD_HEADER) else {
+        return;
+    };
+
+    println!("Recording process {} to {} every {}s (press 'q' to stop)", pid, path, interval);
+    let _interrupts = live::catch_interrupts();
+    let mut rows = 0;
+    loop {
+        let metrics = match get_process_metrics(pid) {
+            Ok(metrics) => metrics,
+            Err(_) => {
+                println!("Process {} has exited", pid);
+                break;
+            }
+        };
+
+        let row = format!(
+            "{},{},{},{},{:.2},{},{},{}",
+            timestamp_now(utc, "%Y-%m-%d %H:%M:%S"),
+            metrics.pid,
+            csv_field(&metrics.comm),
+            csv_field(&metrics.user),
+            metrics.cpu_time,
+            metrics.mem_usage,
+            metrics.io_read_bytes,
+            metrics.io_write_bytes
+        );
+        // Flush every row so a crash doesn't lose the samples taken so far
+        if let Err(e) = writeln!(file, "{}", row).and_then(|_| file.flush()) {
+            println!("Failed to write to {}: {}", path, e);
+            break;
+        }
+        rows += 1;
+
+        if live::wait_for_quit(time::Duration::from_secs(interval)) {
+            break;
+        }
+    }
+    println!("Recorded {} samples to {}", rows, path);
+}
+
+// Consecutive increases in the thread count before `threadgrowth` warns of a leak
+const THREAD_LEAK_SAMPLES: usize = 5;
+
+// Sample a process's thread count every interval until it exits or the user presses `q`
+fn show_thread_growth(pid: u32, interval: u64) {
+    println!("Sampling threads of process {} every {}s (press 'q' to stop)", pid, interval);
+    let mut samples: Vec<u64> = Vec::new();
+    let mut warned = false;
+    live::run_refresh_loop(time::Duration::from_secs(interval), None, || {
+        let threads = match get_thread_count(pid) {
+            Ok(threads) => threads,
+            Err(_) => {
+                println!("Process {} has exited", pid);
+                return false;
+            }
+        };
+        let delta = samples.last().map_or(0, |&last| threads as i64 - last as i64);
+        samples.push(threads);
+        println!(
+            "threads: {:<6} change: {:<+6} rate: {:+.2}/s",
+            threads,
+            delta,
+            rate_per_second(&samples, interval)
+        );
+
+        // Warn once per streak, and again if it grows steadily after levelling off
+        if grows_steadily(&samples, THREAD_LEAK_SAMPLES) {
+            if !warned {
+                println!(
+                    "Warning: thread count rose in each of the last {} intervals; possible thread leak",
+                    THREAD_LEAK_SAMPLES - 1
+                );
+                warned = true;
+            }
+        } else {
+            warned = false;
+        }
+        true
+    });
+    if samples.len() > 1 {
+        println!(
+            "{} samples, {} -> {} threads ({:+.2}/s)",
+            samples.len(),
+            samples[0],
+            samples[samples.len() - 1],
+            rate_per_second(&samples, interval)
+        );
+    }
+}
+
+// Redraw one process every interval: CPU% and I/O are measured since the previous
+// sample (the first shows its lifetime CPU% and no I/O change yet)
+fn watch_process(pid: u32, interval: u64) {
+    let mut previous: Option<(ProcessMetrics, time::Instant)> = None;
+    live::run_refresh_loop(time::Duration::from_secs(interval), None, || {
+        let metrics = match get_process_metrics(pid) {
+            Ok(metrics) => metrics,
+            Err(ProcError::NotFound) if previous.is_none() => {
+                println!("No such process: {}", pid);
+                return false;
+            }
+            Err(ProcError::NotFound) => {
+                println!("process {} has exited", pid);
+                return false;
+            }
+            Err(e) => {
+                println!("Error reading process {}: {}", pid, e);
+                return false;
+            }
+        };
+        let now = time::Instant::now();
+        let (cpu, read_delta, write_delta) = match &previous {
+            // A different comm means the PID was reused, so there's nothing to compare with
+            Some((old, at)) if old.comm == metrics.comm => {
+                let elapsed = now.duration_since(*at).as_secs_f64();
+                let cpu = if elapsed > 0.0 {
+                    ((metrics.cpu_seconds - old.cpu_seconds) / elapsed * 100.0).max(0.0)
+                } else {
+                    0.0
+                };
+                (
+                    cpu,
+                    metrics.io_read_bytes.saturating_sub(old.io_read_bytes),
+                    metrics.io_write_bytes.saturating_sub(old.io_write_bytes),
+                )
+            }
+            _ => (metrics.cpu_time, 0, 0),
+        };
+
+        print!("\x1B[2J\x1B[H"); // Clear screen
+        println!("Watching {} ({}) every {}s, press 'q' to stop", pid, metrics.comm, interval);
+        println!("CPU:    {:.1}%", cpu);
+        println!("Memory: {} ({:.1}%)", format_kb(metrics.mem_usage), metrics.mem_percent);
+        if metrics.io_denied {
+            println!("I/O:    permission denied");
+        } else if !io_accounting() {
+            println!("I/O:    N/A");
+        } else {
+            println!(
+                "Read:   {} B (+{} B)",
+                with_thousands(metrics.io_read_bytes),
+                with_thousands(read_delta)
+            );
+            println!(
+                "Write:  {} B (+{} B)",
+                with_thousands(metrics.io_write_bytes),
+                with_thousands(write_delta)
+            );
+        }
+        io::stdout().flush().unwrap();
+        previous = Some((metrics, now));
+        true
+    });
+}
+
+// PIDs currently in /proc, with their names when they're going to be shown
+fn pid_snapshot(with_names: bool) -> HashMap<u32, String> {
+    list_pids()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|pid| {
+            let name = if with_names {
+                peek_state(pid).map(|(comm, _)| comm).unwrap_or_default()
+            } else {
+                String::new()
+            };
+            (pid, name)
+        })
+        .collect()
+}
+
+fn describe_pids(pids: &[u32], names: &HashMap<u32, String>) -> String {
+    pids.iter()
+        .map(|pid| match names.get(pid) {
+            Some(name) if !name.is_empty() => format!("{}({})", name, pid),
+            _ => pid.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Report process starts/exits every interval, useful to spot fork bombs and crash loops
+fn show_churn(interval: u64, show_names: bool, utc: bool) {
+    println!("Watching process churn every {}s (press 'q' to stop)", interval);
+    let _interrupts = live::catch_interrupts();
+    let mut previous = pid_snapshot(show_names);
+    while !live::wait_for_quit(time::Duration::from_secs(interval)) {
+        let current = pid_snapshot(show_names);
+        let churn = pid_churn(&previous, &current);
+        println!(
+            "{} processes: {:<6} started: {:<4} exited: {}",
+            timestamp_now(utc, "%H:%M:%S"),
+            current.len(),
+            churn.started.len(),
+            churn.exited.len()
+        );
+        if show_names {
+            if !churn.started.is_empty() {
+                println!("  started: {}", describe_pids(&churn.started, &current));
+            }
+            if !churn.exited.is_empty() {
+                println!("  exited:  {}", describe_pids(&churn.exited, &previous));
+            }
+        }
+        previous = current;
+    }
+}
+
+// Rank processes by the CPU time they've burned in total, regardless of current load
+fn show_cpu_share(top: Option<usize>) {
+    let processes = scan_processes();
+    let shares = cpu_shares(&processes);
+
+    println!("{:<8} {:<15} {:<10} {:<12} Share", "PID", "Process", "User", "CPU(s)");
+    for (metrics, share) in shares.iter().take(top.unwrap_or(usize::MAX)) {
+        println!(
+            "{:<8} {:<15} {:<10} {:<12.2} {:.2}%",
+            metrics.pid, metrics.comm, metrics.user, metrics.cpu_seconds, share
+        );
+    }
+}
+
+// Processes that have been pushed to swap, read from smaps_rollup
+fn show_swapping(top: Option<usize>) {
+    let mut unreadable = 0;
+    let usage: Vec<(ProcessMetrics, u64)> = scan_processes()
+        .into_iter()
+        .filter_map(|metrics| match get_smaps_rollup(metrics.pid) {
+            Ok(smaps) => Some((metrics, smaps.swap)),
+            Err(_) => {
+                unreadable += 1;
+                None
+            }
+        })
+        .collect();
+    let swapped = swapped_processes(&usage);
+
+    if swapped.is_empty() {
+        println!("No readable process has memory in swap");
+    } else {
+        println!("{:<8} {:<15} {:<10} {:<12} Swap", "PID", "Process", "User", "RSS");
+        for (metrics, swap) in swapped.iter().take(top.unwrap_or(usize::MAX)) {
+            println!(
+                "{:<8} {:<15} {:<10} {:<12} {}",
+                metrics.pid,
+                metrics.comm,
+                metrics.user,
+                format_kb(metrics.mem_usage),
+                format_kb(*swap)
+            );
+        }
+    }
+    if unreadable > 0 {
+        println!(
+            "Note: swap unavailable for {} processes (smaps_rollup needs Linux 4.14+ and root for other users' processes)",
+            unreadable
+        );
+    }
+}
+
+// Widest histogram bar drawn by `nicedist`
+const HISTOGRAM_WIDTH: usize = 40;
+
+// Histogram of how many processes run at each nice level
+fn show_nice_distribution() {
+    let levels = nice_distribution(&scan_processes());
+    let largest = levels.iter().map(|(_, count)| *count).max().unwrap_or(0);
+
+    println!("{:<6} Processes", "Nice");
+    for (nice, count) in &levels {
+        // Scale to the largest level, but keep at least one mark for non-empty levels
+        let width = (count * HISTOGRAM_WIDTH / largest).max(1);
+        println!("{:<6} {:<10} {}", nice, count, "#".repeat(width));
+    }
+}
+
+// How many processes have been running for under a minute, an hour, a day, or longer
+fn show_age_distribution() {
+    let processes = scan_processes();
+    let buckets = age_distribution(&processes);
+
+    println!("{:<10} {:<10} Share", "Age", "Processes");
+    for (label, count) in buckets {
+        let share = if processes.is_empty() {
+            0.0
+        } else {
+            count as f64 / processes.len() as f64 * 100.0
+        };
+        println!("{:<10} {:<10} {:.1}%", label, count, share);
+    }
+}
+
+// Print Prometheus metrics, or write them for node exporter's textfile collector.
+// The file is replaced with a rename so a scrape never sees it half written.
+fn export_metrics(path: Option<&str>) {
+    let memory = read_meminfo().unwrap_or_default();
+    let text = prometheus::render(&scan_processes(), &memory);
+
+    let Some(path) = path else {
+        print!("{}", text);
+        return;
+    };
+    let tmp_path = format!("{}.tmp", path);
+    match fs::write(&tmp_path, text).and_then(|_| fs::rename(&tmp_path, path)) {
+        Ok(()) => println!("Wrote metrics to {}", path),
+        Err(e) => println!("Failed to write metrics to {}: {}", path, e),
+    }
+}
+
+// How often `waitfor` rescans /proc
+const WAITFOR_POLL: time::Duration = time::Duration::from_millis(500);
+
+// Poll until a process matching the name shows up, the timeout passes, or the user quits
+fn wait_for_process(name: &str, timeout: Option<u64>) {
+    match timeout {
+        Some(secs) => println!("Waiting up to {}s for '{}' (press 'q' to stop)", secs, name),
+        None => println!("Waiting for '{}' (press 'q' to stop)", name),
+    }
+
+    let _interrupts = live::catch_interrupts();
+    let started = time::Instant::now();
+    loop {
+        let found: Vec<ProcessMetrics> = scan_processes()
+            .into_iter()
+            .filter(|metrics| matches_name(&metrics.comm, name, false))
+            .collect();
+        if !found.is_empty() {
+            println!("{}", process_header());
+            for metrics in &found {
+                println!("{}", process_row(metrics));
+            }
+            return;
+        }
+
+        if let Some(secs) = timeout
+            && started.elapsed() >= time::Duration::from_secs(secs)
+        {
+            println!("'{}' not found after {}s", name, secs);
+            return;
+        }
+        if live::wait_for_quit(WAITFOR_POLL) {
+            return;
+        }
+    }
+}
+
+// Sampling window used to measure current CPU usage for one-shot commands
+const CPU_SAMPLE: time::Duration = time::Duration::from_millis(500);
+
+// Scan twice and replace each process's lifetime-average CPU% with its usage
+// over the sampling window
+fn scan_with_current_cpu() -> Vec<ProcessMetrics> {
+    let before = scan_processes();
+    let started = time::Instant::now();
+    std::thread::sleep(CPU_SAMPLE);
+    let mut after = scan_processes();
+
+    let rates = cpu_percent_between(&before, &after, started.elapsed().as_secs_f64());
+    for metrics in after.iter_mut() {
+        metrics.cpu_time = rates.get(&metrics.pid).copied().unwrap_or(0.0);
+    }
+    after
+}
+
+fn show_top_consumer(resource: Resource) {
+    let processes = match resource {
+        Resource::Cpu => scan_with_current_cpu(),
+        Resource::Mem | Resource::Io => scan_processes(),
+    };
+    match top_consumer(&processes, resource) {
+        Some(metrics) => {
+            println!("{}", process_header());
+            println!("{}", process_row(metrics));
+        }
+        None => println!("No processes found"),
+    }
+}
+
+fn print_groups(label: &str, groups: &[(String, usize)], count: bool) {
+    if count {
+        println!("{}", groups.len());
+        return;
+    }
+
+    println!("{:<20} Processes", label);
+    for (key, processes) in groups {
+        println!("{:<20} {}", key, processes);
+    }
+}
+
+// `info -d`: labeled lines instead of the debug dump, adding the command line,
+// threads, virtual memory and start time
+fn print_detailed_info(metrics: &ProcessMetrics) {
+    println!("Process {} ({})", metrics.pid, metrics.comm);
+    println!("  {:<16} {}", "User:", metrics.user);
+    println!("  {:<16} {:.2}% ({:.1}s total)", "CPU:", metrics.cpu_time, metrics.cpu_seconds);
+    println!(
+        "  {:<16} {}",
+        "Resident memory:",
+        with_percent(&format_kb(metrics.mem_usage), metrics.mem_percent)
+    );
+    match get_detailed_info(metrics.pid) {
+        Ok(info) => {
+            let cmdline = if info.cmdline.is_empty() {
+                format!("[{}] (kernel thread)", metrics.comm)
+            } else {
+                info.cmdline.join(" ")
+            };
+            println!("  {:<16} {}", "Command line:", cmdline);
+            println!("  {:<16} {}", "State:", info.state);
+            println!(
+                "  {:<16} {} ({:.0}s ago)",
+                "Started:",
+                started_at(info.elapsed_seconds, false, "%Y-%m-%d %H:%M:%S"),
+                info.elapsed_seconds
+            );
+            println!("  {:<16} {}", "Threads:", info.threads);
+            println!("  {:<16} {}", "Virtual memory:", format_kb(info.vm_size));
+        }
+        Err(e) => println!("  Details unavailable: {}", e),
+    }
+}
+
+// Where a containerised process lives: its PID inside the container's namespace
+// and which container, when either can be told
+fn print_container_info(pid: u32) {
+    match get_namespaced_pids(pid) {
+        Ok(Some(pids)) if pids.len() > 1 => {
+            let chain: Vec<String> = pids.iter().map(u32::to_string).collect();
+            println!(
+                "Namespaced PID: {} (host to innermost: {})",
+                pids[pids.len() - 1],
+                chain.join(" -> ")
+            );
+        }
+        Ok(Some(_)) => println!("Namespaced PID: same as host (not in a nested PID namespace)"),
+        Ok(None) => println!("Namespaced PID: unknown (kernel doesn't report NSpid)"),
+        Err(e) => println!("Namespaced PID: unavailable ({})", e),
+    }
+
+    match cgroup::read_cgroup_paths(pid) {
+        Ok(paths) => match cgroup::detect_container(&paths) {
+            Some(container) => {
+                // Runtimes usually show the first 12 characters of an ID
+                let short: String = container.id.chars().take(12).collect();
+                println!("Container: {} {}", container.runtime, short);
+            }
+            None => println!("Container: none detected"),
+        },
+        Err(e) => println!("Container: unavailable ({})", e),
+    }
+}
+
+// RSS against the process's cgroup memory limit, which is what the OOM killer
+// will hold it to on a container host, or against host RAM without one
+fn print_memory_share(metrics: &ProcessMetrics) {
+    match cgroup::memory_limit_kb(metrics.pid) {
+        Some(limit) => println!(
+            "Memory: {} ({:.1}% of cgroup limit {})",
+            format_kb(metrics.mem_usage),
+            percent_of(metrics.mem_usage, limit),
+            format_kb(limit)
+        ),
+        None => println!(
+            "Memory: {} ({:.1}% of host RAM; no cgroup limit)",
+            format_kb(metrics.mem_usage),
+            metrics.mem_percent
+        ),
+    }
+}
+
+fn show_mem_detail(pid: u32) {
+    match get_smaps_rollup(pid) {
+        Ok(smaps) => {
+            println!("Memory breakdown for PID {}:", pid);
+            println!("  {:<15} {}", "Rss:", format_kb(smaps.rss));
+            println!("  {:<15} {}", "Pss:", format_kb(smaps.pss));
+            println!("  {:<15} {}", "Shared_Clean:", format_kb(smaps.shared_clean));
+            println!("  {:<15} {}", "Shared_Dirty:", format_kb(smaps.shared_dirty));
+            println!("  {:<15} {}", "Private_Clean:", format_kb(smaps.private_clean));
+            println!("  {:<15} {}", "Private_Dirty:", format_kb(smaps.private_dirty));
+            println!("  {:<15} {}", "Swap:", format_kb(smaps.swap));
+        }
+        Err(e) if Path::new(&format!("/proc/{}", pid)).exists() => {
+            // The process exists but the rollup can't be read (kernel < 4.14 or not our process)
+            println!("smaps_rollup is not available for process {}: {}", pid, e);
+            println!("Note: it requires Linux 4.14+ and permission to read the process (try as root)");
+        }
+        Err(e) => println!("Error reading process memory: {}", e),
+    }
+}
+
+// Introspection for the tool itself: how much it costs to run and whether it
+// has the privileges some commands (PSS, other users' I/O) need
+fn show_self() {
+    let pid = std::process::id();
+    let (uid, euid) = unsafe { (libc::getuid(), libc::geteuid()) };
+    match get_process_metrics(pid) {
+        Ok(metrics) => {
+            println!("Linux Process Manager (PID {})", pid);
+            println!("  {:<15} {:.2}% ({:.1}s total)", "CPU:", metrics.cpu_time, metrics.cpu_seconds);
+            println!(
+                "  {:<15} {}",
+                "Memory:",
+                with_percent(&format_kb(metrics.mem_usage), metrics.mem_percent)
+            );
+            if io_accounting() {
+                println!(
+                    "  {:<15} {} read / {} written bytes",
+                    "I/O:", metrics.io_read_bytes, metrics.io_write_bytes
+                );
+            } else {
+                println!("  {:<15} N/A", "I/O:");
+            }
+            println!("  {:<15} {:.0}s", "Running for:", metrics.elapsed_seconds);
+        }
+        Err(e) => println!("Error reading own metrics: {}", e),
+    }
+    println!("  {:<15} {} (real {})", "Effective UID:", euid, uid);
+    if euid == 0 {
+        println!("  {:<15} yes, all processes are readable", "Root:");
+    } else {
+        println!("  {:<15} no, other users' processes may be partly unreadable", "Root:");
+    }
+    // Every setting comes from command flags; nothing is read from a config file
+    println!("  {:<15} none", "Config file:");
+}
+
+// `buffer`: report the current mode, or switch to another one
+fn set_output_buffering(mode: Option<Buffering>) {
+    if let Some(mode) = mode {
+        set_buffering(mode);
+    }
+    match buffering() {
+        Buffering::Line => println!("Output buffering: line (each JSON/CSV record is flushed as it's written)"),
+        Buffering::Block => println!("Output buffering: block (JSON/CSV records are written in large chunks)"),
+    }
+}
+
+// Like `ss -p`: match each process's socket inodes against the kernel's socket tables
+fn show_connections(pid: Option<u32>) {
+    let by_inode: HashMap<u64, net::Connection> = net::read_connections()
+        .into_iter()
+        .map(|connection| (connection.inode, connection))
+        .collect();
+    let single = pid.is_some();
+    let pids = match pid {
+        Some(pid) => vec![pid],
+        None => list_pids().unwrap_or_default(),
+    };
+
+    println!(
+        "{:<8} {:<15} {:<6} {:<40} {:<40} State",
+        "PID", "Process", "Proto", "Local", "Remote"
+    );
+    let mut unreadable = 0;
+    for pid in pids {
+        let inodes = match get_socket_inodes(pid) {
+            Ok(inodes) => inodes,
+            Err(e) if single => {
+                println!("Error reading open files of process {}: {}", pid, e);
+                return;
+            }
+            // It may have exited since listing /proc, or belong to another user
+            Err(ProcError::NotFound) => continue,
+            Err(_) => {
+                unreadable += 1;
+                continue;
+            }
+        };
+        let comm = peek_state(pid).map(|(comm, _)| comm).unwrap_or_default();
+        for connection in inodes.iter().filter_map(|inode| by_inode.get(inode)) {
+            println!(
+                "{:<8} {:<15} {:<6} {:<40} {:<40} {}",
+                pid,
+                comm,
+                connection.proto,
+                connection.local.to_string(),
+                connection.remote.to_string(),
+                connection.state
+            );
+        }
+    }
+    if unreadable > 0 {
+        println!(
+            "Note: could not read open files of {} processes (other users' processes require root)",
+            unreadable
+        );
+    }
+}
+
+fn explain_process(pid: u32) {
+    // Read the state first: it's what tells us whether wchan is worth reporting
+    let state = match peek_state(pid) {
+        Ok((_, state)) => state,
+        Err(e) => {
+            println!("Error reading process state: {}", e);
+            return;
+        }
+    };
+    // wchan needs ptrace access for other users' processes; explain without it
+    let wchan = get_wchan(pid).ok();
+    match get_process_metrics(pid) {
+        Ok(metrics) => println!("{}", explain::explain(&metrics, state, wchan.as_deref())),
+        Err(e) => println!("Error reading process metrics: {}", e),
+    }
+}
+
+// A labelled measure `peers` compares across a pool of same-named processes
+type PeerMeasure = (&'static str, fn(&ProcessMetrics) -> f64);
+
+const PEER_MEASURES: [PeerMeasure; 3] = [
+    ("%CPU", |metrics| metrics.cpu_time),
+    ("Memory(KB)", |metrics| metrics.mem_usage as f64),
+    ("I/O (bytes)", |metrics| (metrics.io_read_bytes + metrics.io_write_bytes) as f64),
+];
+
+// One worker against the rest of its pool (every process with the same name),
+// to spot the one misbehaving php-fpm or gunicorn child
+fn show_peers(pid: u32) {
+    let target = match get_process_metrics(pid) {
+        Ok(metrics) => metrics,
+        Err(e) => {
+            println!("Error reading process metrics: {}", e);
+            return;
+        }
+    };
+    let processes = scan_processes();
+    let peers = same_name(&processes, &target.comm);
+    if peers.iter().all(|metrics| metrics.pid == pid) {
+        println!("No other readable process is named {}", target.comm);
+        return;
+    }
+
+    let color = term::color_enabled();
+    println!("{}", process_header());
+    for metrics in &peers {
+        let row = process_row(metrics);
+        match (metrics.pid == pid, color) {
+            (true, true) => println!("{}", term::paint(&row, Color::Yellow)),
+            (true, false) => println!("{} <", row),
+            (false, _) => println!("{}", row),
+        }
+    }
+
+    println!();
+    println!("{:<12} {:>14} {:>14} {:>14} {:>14}", "", "Min", "Avg", "Max", format!("PID {}", pid));
+    for (label, value) in PEER_MEASURES {
+        if let Some(stats) = spread(peers.iter().map(|metrics| value(metrics))) {
+            println!(
+                "{:<12} {:>14.1} {:>14.1} {:>14.1} {:>14.1}",
+                label,
+                stats.min,
+                stats.avg,
+                stats.max,
+                value(&target)
+            );
+        }
+    }
+}
+
+// Every process under its parent, like pstree. With a sort resource the
+// children of each process are ordered heaviest first, so the costliest
+// branch is the first one at every level.
+fn show_tree(root: Option<u32>, sort_by: Option<&str>) {
+    let resource = match sort_by {
+        Some(key) => match Resource::parse(key) {
+            Some(resource) => Some(resource),
+            None => {
+                println!("Unknown sort key '{}' (expected cpu, mem or io)", key);
+                return;
+            }
+        },
+        None => None,
+    };
+    let processes = scan_processes();
+    let by_pid: HashMap<u32, &ProcessMetrics> = processes.iter().map(|metrics| (metrics.pid, metrics)).collect();
+    if let Some(pid) = root
+        && !by_pid.contains_key(&pid)
+    {
+        println!("No process with PID {}", pid);
+        return;
+    }
+    let mut children = tree::children_map(&processes);
+    if let Some(resource) = resource {
+        tree::sort_children(&mut children, root.unwrap_or(0), &|pid| resource.usage(by_pid[&pid]));
+    }
+
+    let label = |pid| {
+        let metrics = by_pid[&pid];
+        format!("{} {} ({:.1}% CPU, {})", pid, metrics.comm, metrics.cpu_time, format_kb(metrics.mem_usage))
+    };
+    let lines = match root {
+        Some(pid) => tree::render_subtree(&children, pid, label),
+        None => tree::render(&children, 0, label),
+    };
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+// `info --format json|csv`: the process as one record, for scripts
+fn print_process_record(metrics: &ProcessMetrics, detailed: bool, format: OutputFormat) {
+    let mut out = RecordWriter::stdout();
+    // A closed pipe just means nobody wants the rest
+    let _ = match format {
+        OutputFormat::Json { pretty } => {
+            let detail = if detailed { get_detailed_info(metrics.pid).ok() } else { None };
+            out.record(&process_json(metrics, detail.as_ref(), pretty))
+        }
+        OutputFormat::Csv => out
+            .record(PROCESS_CSV_HEADER)
+            .and_then(|_| out.record(&process_csv_row(metrics))),
+        OutputFormat::Table => Ok(()),
+    };
+}
+
+// Why a process is blocked, straight from /proc/[pid]/wchan
+fn show_wchan(pid: u32) {
+    let comm = peek_state(pid).map(|(comm, _)| comm).unwrap_or_default();
+    match get_wchan(pid) {
+        Ok(wchan) => println!("PID {} ({}): {}", pid, comm, explain::wchan_summary(&wchan)),
+        Err(ProcError::PermissionDenied) => {
+            println!("Permission denied: reading another user's wchan needs ptrace access (try root)")
+        }
+        Err(e) => println!("Error reading wchan: {}", e),
+    }
+}
+
+pub fn run() {
+    println!("Linux Process Manager - Rust Edition");
+    println!("Type 'help' for available commands, 'exit' to quit\n");
+    if !io_accounting() {
+        println!("Note: this kernel has no per-process I/O accounting; I/O columns show N/A\n");
+    }
+
+    let parser = CommandParser::new();
+    let mut prompt = match Prompt::new() {
+        Ok(prompt) => prompt,
+        Err(e) => {
+            println!("Error starting the prompt: {}", e);
+            return;
+        }
+    };
+    let mut io_baselines = IoBaselines::new();
+
+    loop {
+        let input = match prompt.read("lpm> ") {
+            Input::Line(line) => line,
+            // Ctrl+C abandons the line being typed, like a shell
+            Input::Interrupted => continue,
+            // Ctrl+D (or the end of piped input) ends the session like 'exit'
+            Input::Eof => {
+                println!("Goodbye!");
+                break;
+            }
+        };
+
+        let result = parser.parse(&input);
+        
+        match result.command {
+            Command::ListProcesses {
+                user,
+                sort_by,
+                pager,
+                filter,
+                exclude,
+                all_threads,
+                weights,
+                both,
+                last_cpu,
+                min_cpu,
+                min_mem,
+                format,
+                ..
+            } => {
+                let filters = ListFilters { filter, exclude, user };
+                let threshold = UsageThreshold { min_cpu, min_mem };
+                if all_threads {
+                    list_threads(pager, &filters);
+                } else {
+                    let options = ListOptions { sort_by, weights, both, last_cpu, pager, format };
+                    list_processes(options, filters, threshold);
+                }
+            }
+            Command::KillProcess { pid, escalate: Some(grace), .. } => {
+                kill_with_escalation(pid, grace);
+            }
+            Command::KillProcess { pid, signal, tid, escalate: None } => {
+                let Some(sig) = requested_signal(signal.as_deref()) else {
+                    continue;
+                };
+                match tid {
+                    Some(tid) => kill_thread(pid, tid, sig),
+                    None => match signal::kill(Pid::from_raw(pid as i32), sig) {
+                        Ok(_) => println!("Successfully killed process {}", pid),
+                        Err(e) => println!("Failed to kill process {}: {}", pid, e),
+                    },
+                }
+            }
+
+            Command::KillByName { name, signal, all, rate } => {
+                if let Some(sig) = requested_signal(signal.as_deref()) {
+                    kill_by_name(&name, sig, all, rate);
+                }
+            }
+            Command::KillTree { pid, signal, rate } => {
+                if let Some(sig) = requested_signal(signal.as_deref()) {
+                    kill_tree(pid, sig, rate);
+                }
+            }
+            Command::KillUser { user, signal, confirmed, force, rate } => {
+                if let Some(sig) = requested_signal(signal.as_deref()) {
+                    kill_user(&user, sig, confirmed, force, rate);
+                }
+            }
+            Command::ProcessInfo { pid, detailed, ancestry, format } => {
+                match get_process_metrics(pid) {
+                    Ok(metrics) if format != OutputFormat::Table => {
+                        print_process_record(&metrics, detailed, format);
+                    }
+                    Ok(metrics) => {
+                        if detailed {
+                            print_detailed_info(&metrics);
+                            print_container_info(pid);
+                            print_memory_share(&metrics);
+                        } else {
+                            println!("{:?}", metrics);
+                        }
+                        if ancestry {
+                            let chain = tree::ancestry(pid, |pid| get_parent(pid).ok());
+                            println!("Ancestry: {}", tree::format_ancestry(&chain));
+                        }
+                        if let Some((read, write)) = io_since_reset(&metrics, &io_baselines) {
+                            println!(
+                                "I/O since reset: read {} bytes, write {} bytes (total: {}/{})",
+                                read, write, metrics.io_read_bytes, metrics.io_write_bytes
+                            );
+                        }
+                    }
+                    Err(ProcError::NotFound) => println!("No such process: {}", pid),
+                    Err(ProcError::PermissionDenied) => {
+                        println!("Permission denied reading process {} (try root)", pid)
+                    }
+                    Err(e) => println!("Error reading process {}: {}", pid, e),
+                }
+            }
+            Command::ResetIo { pid } => {
+                match get_process_metrics(pid) {
+                    Ok(metrics) => {
+                        io_baselines.insert(pid, (metrics.io_read_bytes, metrics.io_write_bytes));
+                        println!(
+                            "I/O baseline for process {} set at {} read / {} written bytes",
+                            pid, metrics.io_read_bytes, metrics.io_write_bytes
+                        );
+                    }
+                    Err(e) => println!("Error reading process metrics: {}", e),
+                }
+            }
+            Command::MemDetail { pid } => {
+                show_mem_detail(pid);
+            }
+            Command::Connections { pid } => {
+                show_connections(pid);
+            }
+            Command::Explain { pid } => {
+                explain_process(pid);
+            }
+            Command::Wchan { pid } => {
+                show_wchan(pid);
+            }
+            Command::Renice { pid, nice } => {
+                renice_process(pid, nice);
+            }
+            Command::Peers { pid } => {
+                show_peers(pid);
+            }
+            Command::StatLog { path, interval, utc } => {
+                log_system_stats(&path, interval, utc);
+            }
+            Command::Tree { root, sort_by } => {
+                show_tree(root, sort_by.as_deref());
+            }
+            Command::SystemStats { refresh_interval, history, no_bar, count, summary_only } => {
+                show_system_stats(
+                    refresh_interval.unwrap_or(0),
+                    history.unwrap_or(DEFAULT_HISTORY),
+                    !no_bar,
+                    count,
+                    summary_only,
+                );
+            }
+
+            Command::SearchProcess { name, exact, count } => {
+                search_processes(&name, exact, count);
+            }
+            Command::Growth { interval, top } => {
+                show_memory_growth(interval, top);
+            }
+            Command::Record { pid, path, interval, utc } => {
+                record_process(pid, &path, interval, utc);
+            }
+            Command::ThreadGrowth { pid, interval } => {
+                show_thread_growth(pid, interval);
+            }
+            Command::Threads { pid } => {
+                show_threads(pid);
+            }
+            Command::Watch { pid, interval } => {
+                watch_process(pid, interval);
+            }
+            Command::Churn { interval, names, utc } => {
+                show_churn(interval, names, utc);
+            }
+            Command::CpuShare { top } => {
+                show_cpu_share(top);
+            }
+            Command::Swapping { top } => {
+                show_swapping(top);
+            }
+            Command::NiceDist => {
+                show_nice_distribution();
+            }
+            Command::AgeDist => {
+                show_age_distribution();
+            }
+            Command::PidStat => {
+                show_pid_usage();
+            }
+            Command::SetUid => {
+                show_setuid_processes();
+            }
+            Command::KernelMem => {
+                show_kernel_memory();
+            }
+            Command::SelfInfo => {
+                show_self();
+            }
+            Command::Buffer { mode } => {
+                set_output_buffering(mode);
+            }
+            Command::Metrics { path } => {
+                export_metrics(path.as_deref());
+            }
+            Command::Top1 { resource } => {
+                show_top_consumer(resource);
+            }
+            Command::WaitFor { name, timeout } => {
+                wait_for_process(&name, timeout);
+            }
+            Command::ByUser { count } => {
+                print_groups("User", &group_by_user(&scan_processes()), count);
+            }
+            Command::ByName { count } => {
+                print_groups("Process", &group_by_name(&scan_processes()), count);
+            }
+            Command::Monitor {
+                interval,
+                both,
+                count,
+                no_clear,
+                sort_by,
+                utc,
+                events,
+                only_changed,
+                adaptive,
+                min_cpu,
+                min_mem,
+                top,
+                format,
+            } => {
+                let options = MonitorOptions {
+                    interval,
+                    both,
+                    count,
+                    no_clear,
+                    sort_by,
+                    utc,
+                    events,
+                    only_changed,
+                    adaptive,
+                    threshold: UsageThreshold { min_cpu, min_mem },
+                    top,
+                    format,
+                };
+                monitor_processes(options, &io_baselines);
+            }
+            Command::Help => {
+                show_help();
+            }
+            Command::Exit => {
+                println!("Goodbye!");
+                break;
+            }
+            Command::Unknown(cmd) => {
+                println!("Unknown command: {}", cmd);
+                if let Some(command) = parser.suggest(&input) {
+                    println!("Did you mean '{}'?", command);
+                }
+                show_help();
+            }
+        }
+    }
+}
+
+fn show_help() {
+    println!("\nAvailable commands:");
+    println!("  ps, list           - List processes (flags: -a/--all, -u/--user USER, -s/--sort FIELD: cpu|mem|io|pid|pss|score|ctxsw,");
+    println!("                       --weights cpu=W,mem=W, --both, --last-cpu, --pager,");
+    println!("                       --filter PATTERN, --exclude PATTERN, --all-threads; patterns may use * and ?,");
+    println!("                       --min-cpu PERCENT, --min-mem KB to hide idle processes,");
+    println!("                       --format table|json|csv for scripts, --pretty to indent JSON)");
+    println!("  kill PID [SIGNAL]  - Kill process with optional signal (SIGHUP, HUP or 1; default SIGTERM)");
+    println!("                       (flags: --tid TID to signal a single thread of the process,");
+    println!("                        --escalate to send SIGTERM then SIGKILL after --grace SECONDS, default 5)");
+    println!("  killuser USER [SIGNAL] - Signal every process of a user (flags: -y to confirm,");
+    println!("                       --force to allow root or yourself, --rate N to send at most N signals per second)");
+    println!("  killall NAME [SIGNAL] - Signal the process with this exact name (name may use * and ?;");
+    println!("                       flags: --all when several match, --rate N to send at most N signals per second)");
+    println!("  killtree PID [SIGNAL] - Signal a process and all of its descendants (flags: --rate N for at most N per second)");
+    println!("  info, show PID     - Show process information (flags: -d/--detailed adds namespace PID and container, --ancestry shows parent chain,");
+    println!("                       --format json|csv for scripts, --pretty to indent JSON)");
+    println!("  stats, status      - Show system statistics (flags: --refresh SECONDS, --count N, --history N, --no-bar,");
+    println!("                       --summary-only for one cpu=/mem=/swap=/load= line per refresh)");
+    println!("  mem PID            - Show memory breakdown (Rss/Pss/Shared/Private/Swap)");
+    println!("  kmem               - Show kernel memory use (slab, kernel stacks, page tables)");
+    println!("  pidstat            - Show how many PIDs are in use out of pid_max");
+    println!("  setuid             - List processes whose effective UID differs from their real UID (root in red)");
+    println!("  connections [PID]  - Show TCP/UDP sockets per process (all processes without PID; root sees every user's)");
+    println!("  explain PID        - Describe in one sentence what a process is doing and waiting on");
+    println!("  wchan PID          - Show the kernel function a process is sleeping in");
+    println!("  renice PID NICE    - Set a process's nice value, from -20 (highest priority) to 19 (lowest)");
+    println!("  peers PID          - Compare a process's CPU, memory and I/O with same-named processes (min/avg/max)");
+    println!("  tree [PID]         - Show processes nested under their parents, or only PID's subtree");
+    println!("                       (flags: --sort cpu|mem|io to list each level's heaviest child first)");
+    println!("  resetio PID        - Record current I/O as a baseline for info/monitor");
+    println!("  search, find NAME  - Search for process by name (flags: -e/--exact, -c/--count; NAME may use * and ?)");
+    println!("  growth [SECONDS]   - Show processes whose memory grew the most (flags: --top N)");
+    println!("  record PID FILE [SECONDS] - Append a process's metrics to a CSV file until 'q' (flags: --utc)");
+    println!("  statlog FILE [SECONDS] - Append system CPU/memory/swap/load to a CSV file until 'q' (flags: --utc)");
+    println!("  threadgrowth PID [SECONDS] - Sample a process's thread count until 'q' to catch thread leaks");
+    println!("  threads PID        - List a process's threads with their names and states");
+    println!("  watch PID [SECONDS] - Follow one process's CPU%, memory and I/O deltas until 'q' or it exits");
+    println!("  churn [SECONDS]    - Count process starts/exits per interval until 'q' (flags: --names, --utc)");
+    println!("  cpushare           - Rank processes by share of all CPU time used (flags: --top N)");
+    println!("  swapping           - List processes with memory in swap, most first (flags: --top N)");
+    println!("  top1 cpu|mem|io    - Show the single heaviest consumer of a resource");
+    println!("  waitfor NAME       - Wait until a matching process appears (flags: --timeout SECONDS)");
+    println!("  byuser             - Count processes per user (flags: --count)");
+    println!("  byname             - Count processes per name (flags: --count)");
+    println!("  nicedist           - Histogram of processes per nice level");
+    println!("  agedist            - Count processes by how long they've been running");
+    println!("  metrics [FILE]     - Prometheus-format metrics, printed or written to FILE");
+    println!("  self               - Show this tool's own PID, resource usage and privileges");
+    println!("  buffer [line|block] - Show or set how --format json|csv records are flushed: each at once (line)");
+    println!("                       or in large writes (block)");
+    println!("  Monitor (Seconds)  - Live process monitor (refresh every N seconds, 'q' or Ctrl-C to stop, '/' to filter by name, Esc to clear)");
+    println!("                       (flags: -b/--both for absolute and percent usage, --count N to stop after N refreshes,");
+    println!("                        --no-clear to print refreshes one after another,");
+    println!("                        --sort ctxsw to rank by context switches per second,");
+    println!("                        --utc for UTC timestamps instead of local time,");
+    println!("                        --events to follow process starts/exits and re-read only new processes in full,");
+    println!("                        refreshing the rest from stat (I/O updates every 10th refresh, or every");
+    println!("                        refresh with --sort io, --format json|csv or a resetio baseline); needs root,");
+    println!("                        --only-changed to show only processes whose CPU, memory or state changed,");
+    println!("                        --min-cpu PERCENT, --min-mem KB to hide idle processes,");
+    println!("                        --top N to show only the N heaviest, by CPU or by --sort cpu|mem,");
+    println!("                        --format json|csv to print a record per refresh, --pretty to indent JSON,");
+    println!("                        --adaptive to refresh every --min-interval S (default 1) when idle, stretching");
+    println!("                        to --max-interval S (default 10) as global CPU usage nears 100%)");
+    println!("  help               - Show this help message");
+    println!("  exit, quit         - Exit the program");
+    println!();
+}
\ No newline at end of file
@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::Path;
+
+use crate::proc_reader::ProcError;
+
+// A container a process was found to run in, judged by its cgroup path
+#[derive(Debug, PartialEq)]
+pub struct Container {
+    pub runtime: &'static str,
+    pub id: String,
+}
+
+// Unit-name prefixes container runtimes use for the cgroup of each container
+const RUNTIME_PREFIXES: [(&str, &str); 4] = [
+    ("docker-", "docker"),
+    ("cri-containerd-", "containerd"),
+    ("crio-", "cri-o"),
+    ("libpod-", "podman"),
+];
+
+// The cgroup paths of a process, one per hierarchy (just one on cgroup v2)
+pub fn read_cgroup_paths(pid: u32) -> Result<Vec<String>, ProcError> {
+    let content = fs::read_to_string(format!("/proc/{}/cgroup", pid))?;
+    Ok(parse_cgroup_paths(&content))
+}
+
+// Lines look like `hierarchy-id:controllers:path`; the path may itself contain ':'
+fn parse_cgroup_paths(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .map(str::to_string)
+        .collect()
+}
+
+// Where a process's memory controller lives, relative to /sys/fs/cgroup: the
+// v1 `memory` hierarchy if mounted, else the v2 unified one (`0::path`)
+fn memory_cgroup(content: &str) -> Option<(String, &'static str)> {
+    let mut unified = None;
+    for line in content.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(id), Some(controllers), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if controllers.split(',').any(|c| c == "memory") {
+            return Some((format!("memory{}", path), "memory.limit_in_bytes"));
+        }
+        if id == "0" && controllers.is_empty() {
+            unified = Some((path.trim_start_matches('/').to_string(), "memory.max"));
+        }
+    }
+    unified
+}
+
+// A limit file's value in bytes. v2 writes "max" for no limit; v1 writes a
+// huge number (LONG_MAX rounded down to a page), so anything past 2^60 counts
+// as unlimited too.
+fn parse_memory_limit(content: &str) -> Option<u64> {
+    let bytes: u64 = content.trim().parse().ok()?;
+    (bytes < 1 << 60).then_some(bytes)
+}
+
+// The memory limit that applies to a process in KB, if any. Limits of parent
+// cgroups apply too, so this is the tightest one on the way up to the root.
+pub fn memory_limit_kb(pid: u32) -> Option<u64> {
+    let content = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    let (relative, file) = memory_cgroup(&content)?;
+    let root = Path::new("/sys/fs/cgroup");
+    let dir = root.join(relative.trim_start_matches('/'));
+    dir.ancestors()
+        .take_while(|ancestor| ancestor.starts_with(root))
+        .filter_map(|ancestor| fs::read_to_string(ancestor.join(file)).ok())
+        .filter_map(|content| parse_memory_limit(&content))
+        .min()
+        .map(|bytes| bytes / 1024)
+}
+
+fn is_container_id(id: &str) -> bool {
+    id.len() >= 32 && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Recognise the common runtimes' layouts: systemd scopes like
+// `/system.slice/docker-<id>.scope`, and plain `/docker/<id>`, `/kubepods/.../<id>`
+// or `/lxc/<name>` directories
+pub fn detect_container(paths: &[String]) -> Option<Container> {
+    for path in paths {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        for (i, segment) in segments.iter().enumerate() {
+            let unit = segment.trim_end_matches(".scope");
+            for (prefix, runtime) in RUNTIME_PREFIXES {
+                if let Some(id) = unit.strip_prefix(prefix)
+                    && is_container_id(id)
+                {
+                    return Some(Container { runtime, id: id.to_string() });
+                }
+            }
+
+            let parent = if i > 0 { segments[i - 1] } else { "" };
+            if is_container_id(unit) {
+                let runtime = if segments.iter().any(|s| s.starts_with("kubepods")) {
+                    "kubernetes"
+                } else if parent == "docker" {
+                    "docker"
+                } else {
+                    continue;
+                };
+                return Some(Container { runtime, id: unit.to_string() });
+            }
+            if parent == "lxc" || parent == "lxc.payload" {
+                return Some(Container { runtime: "lxc", id: unit.to_string() });
+            }
+            if let Some(name) = unit.strip_prefix("lxc.payload.") {
+                return Some(Container { runtime: "lxc", id: name.to_string() });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "3f4e1b2c9a8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f";
+
+    fn detect(path: &str) -> Option<Container> {
+        detect_container(&[path.to_string()])
+    }
+
+    #[test]
+    fn test_parse_cgroup_paths() {
+        let paths = parse_cgroup_paths("12:memory:/docker/abc\n0::/user.slice/a:b.scope\n");
+        assert_eq!(paths, vec!["/docker/abc", "/user.slice/a:b.scope"]);
+    }
+
+    #[test]
+    fn test_memory_cgroup() {
+        let v1 = "5:devices:/\n4:memory:/docker/abc\n0::/\n";
+        assert_eq!(memory_cgroup(v1), Some(("memory/docker/abc".to_string(), "memory.limit_in_bytes")));
+        let v2 = "0::/system.slice/docker-abc.scope\n";
+        assert_eq!(memory_cgroup(v2), Some(("system.slice/docker-abc.scope".to_string(), "memory.max")));
+        assert_eq!(memory_cgroup("3:cpu,cpuacct:/\n"), None);
+    }
+
+    #[test]
+    fn test_parse_memory_limit() {
+        assert_eq!(parse_memory_limit("268435456\n"), Some(268435456));
+        assert_eq!(parse_memory_limit("max\n"), None);
+        assert_eq!(parse_memory_limit("9223372036854771712\n"), None);
+    }
+
+    #[test]
+    fn test_detect_container() {
+        let docker = Some(Container { runtime: "docker", id: ID.to_string() });
+        assert_eq!(detect(&format!("/system.slice/docker-{}.scope", ID)), docker);
+        assert_eq!(detect(&format!("/docker/{}", ID)), docker);
+        assert_eq!(
+            detect(&format!("/kubepods.slice/kubepods-pod1.slice/cri-containerd-{}.scope", ID)),
+            Some(Container { runtime: "containerd", id: ID.to_string() })
+        );
+        assert_eq!(
+            detect(&format!("/kubepods/besteffort/pod1234/{}", ID)),
+            Some(Container { runtime: "kubernetes", id: ID.to_string() })
+        );
+        assert_eq!(
+            detect("/lxc.payload.web1"),
+            Some(Container { runtime: "lxc", id: "web1".to_string() })
+        );
+        assert_eq!(detect("/user.slice/user-1000.slice/session-2.scope"), None);
+        assert_eq!(detect("/"), None);
+    }
+}
@@ -0,0 +1,341 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::proc_reader::ProcessMetrics;
+
+#[derive(Debug, PartialEq)]
+pub struct MemoryGrowth {
+    pub pid: u32,
+    pub comm: String,
+    pub before_kb: u64,
+    pub after_kb: u64,
+}
+
+impl MemoryGrowth {
+    pub fn delta_kb(&self) -> i64 {
+        self.after_kb as i64 - self.before_kb as i64
+    }
+
+    pub fn percent(&self) -> f64 {
+        if self.before_kb == 0 {
+            0.0
+        } else {
+            self.delta_kb() as f64 / self.before_kb as f64 * 100.0
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GrowthReport {
+    // Processes present in both samples whose RSS grew, largest growth first
+    pub grown: Vec<MemoryGrowth>,
+    pub started: usize,
+    pub exited: usize,
+}
+
+// Compare two RSS samples. A PID whose name changed was reused by a new
+// process, so it counts as one exit plus one start rather than as growth.
+pub fn memory_growth(before: &[ProcessMetrics], after: &[ProcessMetrics]) -> GrowthReport {
+    let earlier: HashMap<u32, &ProcessMetrics> = before.iter().map(|m| (m.pid, m)).collect();
+    let mut report = GrowthReport::default();
+    let mut survivors = 0;
+
+    for metrics in after {
+        match earlier.get(&metrics.pid) {
+            Some(old) if old.comm == metrics.comm => {
+                survivors += 1;
+                if metrics.mem_usage > old.mem_usage {
+                    report.grown.push(MemoryGrowth {
+                        pid: metrics.pid,
+                        comm: metrics.comm.clone(),
+                        before_kb: old.mem_usage,
+                        after_kb: metrics.mem_usage,
+                    });
+                }
+            }
+            _ => report.started += 1,
+        }
+    }
+    report.exited = before.len() - survivors;
+
+    report
+        .grown
+        .sort_by(|a, b| b.delta_kb().cmp(&a.delta_kb()).then_with(|| a.pid.cmp(&b.pid)));
+    report
+}
+
+// CPU% of each process between two samples of its cumulative CPU time taken
+// `elapsed` seconds apart. Processes new in the second sample (or PIDs reused
+// by a different program) have no baseline and get 0.
+pub fn cpu_percent_between(
+    before: &[ProcessMetrics],
+    after: &[ProcessMetrics],
+    elapsed: f64,
+) -> HashMap<u32, f64> {
+    let earlier: HashMap<u32, &ProcessMetrics> = before.iter().map(|m| (m.pid, m)).collect();
+    after
+        .iter()
+        .map(|metrics| {
+            let percent = match earlier.get(&metrics.pid) {
+                Some(old) if old.comm == metrics.comm && elapsed > 0.0 => {
+                    ((metrics.cpu_seconds - old.cpu_seconds) / elapsed * 100.0).max(0.0)
+                }
+                _ => 0.0,
+            };
+            (metrics.pid, percent)
+        })
+        .collect()
+}
+
+// Context switches per second between two samples
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CtxSwitchRate {
+    pub voluntary: f64,
+    pub nonvoluntary: f64,
+}
+
+impl CtxSwitchRate {
+    pub fn total(&self) -> f64 {
+        self.voluntary + self.nonvoluntary
+    }
+}
+
+// Context switch rate of each process between two samples taken `elapsed`
+// seconds apart. Like `cpu_percent_between`, processes without a baseline get 0.
+pub fn ctxsw_rate_between(
+    before: &[ProcessMetrics],
+    after: &[ProcessMetrics],
+    elapsed: f64,
+) -> HashMap<u32, CtxSwitchRate> {
+    let earlier: HashMap<u32, &ProcessMetrics> = before.iter().map(|m| (m.pid, m)).collect();
+    after
+        .iter()
+        .map(|metrics| {
+            let rate = match earlier.get(&metrics.pid) {
+                Some(old) if old.comm == metrics.comm && elapsed > 0.0 => {
+                    let per_second = |now: u64, then: u64| now.saturating_sub(then) as f64 / elapsed;
+                    CtxSwitchRate {
+                        voluntary: per_second(
+                            metrics.voluntary_ctxt_switches,
+                            old.voluntary_ctxt_switches,
+                        ),
+                        nonvoluntary: per_second(
+                            metrics.nonvoluntary_ctxt_switches,
+                            old.nonvoluntary_ctxt_switches,
+                        ),
+                    }
+                }
+                _ => CtxSwitchRate::default(),
+            };
+            (metrics.pid, rate)
+        })
+        .collect()
+}
+
+// How a process's displayed CPU%, memory and state moved between two samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessChange {
+    pub cpu: Ordering,
+    pub mem: Ordering,
+    pub state: bool,
+}
+
+impl ProcessChange {
+    pub fn changed(&self) -> bool {
+        self.cpu != Ordering::Equal || self.mem != Ordering::Equal || self.state
+    }
+
+    // Overall direction: up if either value grew, down if one shrank and
+    // neither grew, equal when only the state changed (or nothing did)
+    pub fn direction(&self) -> Ordering {
+        if self.cpu == Ordering::Greater || self.mem == Ordering::Greater {
+            Ordering::Greater
+        } else if self.cpu == Ordering::Less || self.mem == Ordering::Less {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+// Changes for each process present in both samples. New processes (and PIDs
+// reused by a different program) have nothing to compare against and are left out.
+pub fn process_changes(
+    before: &[ProcessMetrics],
+    after: &[ProcessMetrics],
+) -> HashMap<u32, ProcessChange> {
+    let earlier: HashMap<u32, &ProcessMetrics> = before.iter().map(|m| (m.pid, m)).collect();
+    after
+        .iter()
+        .filter_map(|metrics| {
+            let old = earlier.get(&metrics.pid).filter(|old| old.comm == metrics.comm)?;
+            let change = ProcessChange {
+                cpu: metrics.cpu_time.total_cmp(&old.cpu_time),
+                mem: metrics.mem_usage.cmp(&old.mem_usage),
+                state: metrics.state != old.state,
+            };
+            Some((metrics.pid, change))
+        })
+        .collect()
+}
+
+// True when each of the last `window` samples is larger than the one before,
+// the signature of a leak rather than a pool that grows and shrinks
+pub fn grows_steadily(samples: &[u64], window: usize) -> bool {
+    if window < 2 || samples.len() < window {
+        return false;
+    }
+    samples[samples.len() - window..].windows(2).all(|pair| pair[1] > pair[0])
+}
+
+// Net change per second between the first and last of samples taken
+// `interval` seconds apart
+pub fn rate_per_second(samples: &[u64], interval: u64) -> f64 {
+    match (samples.first(), samples.last()) {
+        (Some(&first), Some(&last)) if samples.len() > 1 && interval > 0 => {
+            (last as f64 - first as f64) / ((samples.len() - 1) as u64 * interval) as f64
+        }
+        _ => 0.0,
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PidChurn {
+    pub started: Vec<u32>,
+    pub exited: Vec<u32>,
+}
+
+// Diff two PID snapshots to find which processes started and exited in between
+pub fn pid_churn<V>(before: &HashMap<u32, V>, after: &HashMap<u32, V>) -> PidChurn {
+    let mut churn = PidChurn {
+        started: after.keys().filter(|pid| !before.contains_key(pid)).copied().collect(),
+        exited: before.keys().filter(|pid| !after.contains_key(pid)).copied().collect(),
+    };
+    churn.started.sort_unstable();
+    churn.exited.sort_unstable();
+    churn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, comm: &str, mem_usage: u64) -> ProcessMetrics {
+        ProcessMetrics {
+            pid,
+            comm: comm.to_string(),
+            mem_usage,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_memory_growth() {
+        let before = vec![
+            process(1, "init", 1000),
+            process(2, "leaky", 2000),
+            process(3, "gone", 500),
+            process(4, "old", 100),
+        ];
+        let after = vec![
+            process(1, "init", 1100),
+            process(2, "leaky", 6000),
+            process(4, "reused", 9000),
+            process(5, "new", 300),
+        ];
+
+        let report = memory_growth(&before, &after);
+        let grown: Vec<(u32, i64)> = report.grown.iter().map(|g| (g.pid, g.delta_kb())).collect();
+        assert_eq!(grown, vec![(2, 4000), (1, 100)]);
+        assert_eq!(report.grown[0].percent(), 200.0);
+        assert_eq!(report.started, 2);
+        assert_eq!(report.exited, 2);
+    }
+
+    #[test]
+    fn test_cpu_percent_between() {
+        let mut before = vec![process(1, "busy", 0), process(2, "idle", 0)];
+        before[0].cpu_seconds = 10.0;
+        before[1].cpu_seconds = 5.0;
+        let mut after = vec![process(1, "busy", 0), process(2, "idle", 0), process(3, "new", 0)];
+        after[0].cpu_seconds = 11.0;
+        after[1].cpu_seconds = 5.0;
+        after[2].cpu_seconds = 3.0;
+
+        let rates = cpu_percent_between(&before, &after, 2.0);
+        assert_eq!(rates[&1], 50.0);
+        assert_eq!(rates[&2], 0.0);
+        assert_eq!(rates[&3], 0.0);
+    }
+
+    #[test]
+    fn test_ctxsw_rate_between() {
+        let mut before = vec![process(1, "thrash", 0), process(2, "reused", 0)];
+        before[0].voluntary_ctxt_switches = 100;
+        before[0].nonvoluntary_ctxt_switches = 50;
+        before[1].voluntary_ctxt_switches = 10_000;
+        let mut after = vec![process(1, "thrash", 0), process(2, "other", 0)];
+        after[0].voluntary_ctxt_switches = 300;
+        after[0].nonvoluntary_ctxt_switches = 450;
+        after[1].voluntary_ctxt_switches = 20;
+
+        let rates = ctxsw_rate_between(&before, &after, 2.0);
+        assert_eq!(rates[&1], CtxSwitchRate { voluntary: 100.0, nonvoluntary: 200.0 });
+        assert_eq!(rates[&1].total(), 300.0);
+        assert_eq!(rates[&2], CtxSwitchRate::default());
+    }
+
+    #[test]
+    fn test_process_changes() {
+        let mut before = vec![
+            process(1, "idle", 1000),
+            process(2, "growing", 1000),
+            process(3, "busy", 1000),
+            process(4, "waking", 1000),
+            process(5, "old", 1000),
+        ];
+        before[2].cpu_time = 50.0;
+        before[3].state = 'S';
+        let mut after = vec![
+            process(1, "idle", 1000),
+            process(2, "growing", 3000),
+            process(3, "busy", 1000),
+            process(4, "waking", 1000),
+            process(5, "reused", 1000),
+            process(6, "new", 1000),
+        ];
+        after[2].cpu_time = 20.0;
+        after[3].state = 'R';
+
+        let changes = process_changes(&before, &after);
+        assert!(!changes[&1].changed());
+        assert_eq!(changes[&2].direction(), Ordering::Greater);
+        assert_eq!(changes[&3].cpu, Ordering::Less);
+        assert_eq!(changes[&3].direction(), Ordering::Less);
+        assert!(changes[&4].changed());
+        assert_eq!(changes[&4].direction(), Ordering::Equal);
+        assert!(!changes.contains_key(&5));
+        assert!(!changes.contains_key(&6));
+    }
+
+    #[test]
+    fn test_thread_growth() {
+        assert!(grows_steadily(&[10, 4, 5, 6, 7], 4));
+        assert!(!grows_steadily(&[4, 5, 5, 7], 4));
+        assert!(!grows_steadily(&[4, 5, 6], 4));
+        assert!(!grows_steadily(&[9, 8, 7, 6], 4));
+
+        assert_eq!(rate_per_second(&[10, 12, 14, 20], 2), 10.0 / 6.0);
+        assert_eq!(rate_per_second(&[20, 10], 5), -2.0);
+        assert_eq!(rate_per_second(&[10], 1), 0.0);
+    }
+
+    #[test]
+    fn test_pid_churn() {
+        let before: HashMap<u32, ()> = [(1, ()), (2, ()), (3, ())].into_iter().collect();
+        let after: HashMap<u32, ()> = [(1, ()), (3, ()), (7, ()), (5, ())].into_iter().collect();
+
+        let churn = pid_churn(&before, &after);
+        assert_eq!(churn, PidChurn { started: vec![5, 7], exited: vec![2] });
+    }
+}
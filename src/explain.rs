@@ -0,0 +1,150 @@
+use crate::format::format_kb;
+use crate::proc_reader::ProcessMetrics;
+
+// What a state character from /proc/[pid]/stat means, phrased to follow "is"
+pub fn describe_state(state: char) -> &'static str {
+    match state {
+        'R' => "running",
+        'S' => "sleeping",
+        'D' => "stuck in uninterruptible sleep",
+        'T' => "stopped",
+        't' => "stopped by a debugger",
+        'Z' => "a zombie waiting for its parent to reap it",
+        'X' => "exiting",
+        'I' => "idle",
+        'P' => "parked",
+        _ => "in an unknown state",
+    }
+}
+
+// Kernel functions a process commonly sleeps in, and what it's waiting for.
+// Matched as prefixes since the exact symbol varies between kernel versions.
+const WCHAN_REASONS: [(&str, &str); 14] = [
+    ("sk_wait_data", "a socket read"),
+    ("unix_stream_read", "a socket read"),
+    ("inet_csk_accept", "an accept on a listening socket"),
+    ("pipe_read", "a pipe read"),
+    ("pipe_wait", "a pipe read"),
+    ("wait_woken", "a terminal or socket read"),
+    ("n_tty_read", "a terminal read"),
+    ("ep_poll", "an epoll wait"),
+    ("do_select", "a select call"),
+    ("do_sys_poll", "a poll call"),
+    ("futex_wait", "a lock (futex)"),
+    ("do_wait", "a wait for a child process"),
+    ("hrtimer_nanosleep", "a timed sleep"),
+    ("do_nanosleep", "a timed sleep"),
+];
+
+// Plain-English reason for a wchan symbol. The kernel reports "0" (or nothing)
+// when the process isn't blocked in the kernel, which has no reason to give.
+pub fn describe_wchan(wchan: &str) -> Option<String> {
+    let wchan = wchan.trim();
+    if wchan.is_empty() || wchan == "0" {
+        return None;
+    }
+    let reason = WCHAN_REASONS
+        .iter()
+        .find(|(symbol, _)| wchan.starts_with(symbol))
+        .map(|(_, reason)| reason.to_string())
+        .unwrap_or_else(|| format!("the kernel function {}", wchan));
+    Some(reason)
+}
+
+// What `wchan PID` reports: the symbol, plus what it means when it's a known one
+pub fn wchan_summary(wchan: &str) -> String {
+    let symbol = wchan.trim();
+    match describe_wchan(symbol) {
+        None => "running / not blocked".to_string(),
+        Some(reason) if WCHAN_REASONS.iter().any(|(known, _)| symbol.starts_with(known)) => {
+            format!("blocked in {} ({})", symbol, reason)
+        }
+        Some(_) => format!("blocked in {}", symbol),
+    }
+}
+
+// One sentence summing up what a process is doing, e.g. "PID 123 (nginx) is
+// sleeping in a socket read, using 4% CPU and 120.0 MB RSS"
+pub fn explain(metrics: &ProcessMetrics, state: char, wchan: Option<&str>) -> String {
+    let mut sentence = format!("PID {} ({}) is {}", metrics.pid, metrics.comm, describe_state(state));
+    // Only a blocked process is waiting on anything
+    if matches!(state, 'S' | 'D')
+        && let Some(reason) = wchan.and_then(describe_wchan)
+    {
+        sentence.push_str(&format!(" in {}", reason));
+    }
+    sentence.push_str(&format!(
+        ", using {:.0}% CPU and {} RSS",
+        metrics.cpu_time,
+        format_kb(metrics.mem_usage)
+    ));
+    if metrics.io_read_bytes + metrics.io_write_bytes > 0 {
+        sentence.push_str(&format!(
+            ", having read {} and written {}",
+            format_kb(metrics.io_read_bytes / 1024),
+            format_kb(metrics.io_write_bytes / 1024)
+        ));
+    }
+    sentence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nginx() -> ProcessMetrics {
+        ProcessMetrics {
+            pid: 123,
+            comm: "nginx".to_string(),
+            cpu_time: 4.0,
+            mem_usage: 120 * 1024,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_describe_wchan() {
+        assert_eq!(describe_wchan("sk_wait_data"), Some("a socket read".to_string()));
+        assert_eq!(describe_wchan("futex_wait_queue"), Some("a lock (futex)".to_string()));
+        assert_eq!(
+            describe_wchan("jbd2_log_wait_commit"),
+            Some("the kernel function jbd2_log_wait_commit".to_string())
+        );
+        assert_eq!(describe_wchan("0"), None);
+        assert_eq!(describe_wchan(""), None);
+    }
+
+    #[test]
+    fn test_wchan_summary() {
+        assert_eq!(wchan_summary("do_select\n"), "blocked in do_select (a select call)");
+        assert_eq!(wchan_summary("jbd2_log_wait_commit"), "blocked in jbd2_log_wait_commit");
+        assert_eq!(wchan_summary("0"), "running / not blocked");
+        assert_eq!(wchan_summary(""), "running / not blocked");
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            explain(&nginx(), 'S', Some("sk_wait_data")),
+            "PID 123 (nginx) is sleeping in a socket read, using 4% CPU and 120.0 MB RSS"
+        );
+        // A zero wchan and a running process both leave out the reason
+        assert_eq!(
+            explain(&nginx(), 'S', Some("0")),
+            "PID 123 (nginx) is sleeping, using 4% CPU and 120.0 MB RSS"
+        );
+        assert_eq!(
+            explain(&nginx(), 'R', Some("ep_poll")),
+            "PID 123 (nginx) is running, using 4% CPU and 120.0 MB RSS"
+        );
+
+        let mut busy = nginx();
+        busy.io_read_bytes = 2048;
+        busy.io_write_bytes = 3 * 1024 * 1024;
+        assert_eq!(
+            explain(&busy, 'D', None),
+            "PID 123 (nginx) is stuck in uninterruptible sleep, using 4% CPU and 120.0 MB RSS, \
+             having read 2 KB and written 3.0 MB"
+        );
+    }
+}
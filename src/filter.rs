@@ -0,0 +1,197 @@
+use crate::proc_reader::ProcessMetrics;
+
+// Name matching shared by the commands that look processes up by name.
+// Patterns containing `*` or `?` are shell-style globs matched against the
+// whole name; otherwise it's an exact comparison when asked for, or else a
+// case-insensitive substring match.
+pub fn matches_name(comm: &str, pattern: &str, exact: bool) -> bool {
+    if is_glob(pattern) {
+        if exact {
+            glob_match(pattern, comm)
+        } else {
+            glob_match(&pattern.to_lowercase(), &comm.to_lowercase())
+        }
+    } else if exact {
+        comm == pattern
+    } else {
+        comm.to_lowercase().contains(&pattern.to_lowercase())
+    }
+}
+
+// Whether `ps -u USER` lists a process: USER is a name, or a numeric UID
+// compared with the raw UID. A process whose owner we couldn't read never matches.
+pub fn owned_by(metrics: &ProcessMetrics, user: &str) -> bool {
+    let Some(uid) = metrics.uid else {
+        return false;
+    };
+    match user.parse::<u32>() {
+        Ok(wanted) => uid == wanted,
+        Err(_) => metrics.user == user,
+    }
+}
+
+// The processes `search` shows, in scan order
+pub fn search<'a>(processes: &'a [ProcessMetrics], pattern: &str, exact: bool) -> Vec<&'a ProcessMetrics> {
+    processes
+        .iter()
+        .filter(|metrics| matches_name(&metrics.comm, pattern, exact))
+        .collect()
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+// Match `text` against a glob where `*` is any run of characters and `?` is
+// exactly one character. Backtracks to the last `*` on a mismatch.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            last_star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = last_star {
+            // Let the star swallow one more character and retry
+            p = star_p + 1;
+            t = star_t + 1;
+            last_star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// Minimum usage a process needs to show up in `ps --min-cpu/--min-mem`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UsageThreshold {
+    pub min_cpu: Option<f64>,
+    pub min_mem: Option<u64>,
+}
+
+impl UsageThreshold {
+    pub fn allows(&self, metrics: &ProcessMetrics) -> bool {
+        self.min_cpu.is_none_or(|min| metrics.cpu_time >= min)
+            && self.min_mem.is_none_or(|min| metrics.mem_usage >= min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_threshold() {
+        let idle = ProcessMetrics { cpu_time: 0.0, mem_usage: 512, ..Default::default() };
+        let busy = ProcessMetrics { cpu_time: 12.5, mem_usage: 2048, ..Default::default() };
+
+        assert!(UsageThreshold::default().allows(&idle));
+
+        let cpu = UsageThreshold { min_cpu: Some(5.0), min_mem: None };
+        assert!(!cpu.allows(&idle));
+        assert!(cpu.allows(&busy));
+
+        // Both limits must be met, and each is inclusive
+        let both = UsageThreshold { min_cpu: Some(12.5), min_mem: Some(4096) };
+        assert!(!both.allows(&busy));
+        let both = UsageThreshold { min_cpu: Some(12.5), min_mem: Some(2048) };
+        assert!(both.allows(&busy));
+    }
+
+    #[test]
+    fn test_matches_name() {
+        assert!(matches_name("nginx", "nginx", true));
+        assert!(!matches_name("nginx", "NGINX", true));
+        assert!(!matches_name("nginx: worker", "nginx", true));
+        assert!(matches_name("nginx: worker", "NGINX", false));
+        assert!(!matches_name("bash", "nginx", false));
+    }
+
+    #[test]
+    fn test_owned_by() {
+        let root = ProcessMetrics { user: "root".to_string(), uid: Some(0), ..Default::default() };
+        assert!(owned_by(&root, "root"));
+        assert!(owned_by(&root, "0"));
+        assert!(!owned_by(&root, "1000"));
+        assert!(!owned_by(&root, "www-data"));
+
+        // Without a passwd entry the name is the UID itself
+        let container = ProcessMetrics { user: "1000".to_string(), uid: Some(1000), ..Default::default() };
+        assert!(owned_by(&container, "1000"));
+
+        let unknown = ProcessMetrics { user: "?".to_string(), uid: None, ..Default::default() };
+        assert!(!owned_by(&unknown, "?"));
+    }
+
+    #[test]
+    fn test_search() {
+        let processes: Vec<ProcessMetrics> = ["nginx", "bash", "nginx: worker"]
+            .iter()
+            .enumerate()
+            .map(|(i, comm)| ProcessMetrics { pid: i as u32 + 1, comm: comm.to_string(), ..Default::default() })
+            .collect();
+        let pids = |pattern, exact| -> Vec<u32> {
+            search(&processes, pattern, exact).iter().map(|metrics| metrics.pid).collect()
+        };
+
+        assert_eq!(pids("NGINX", false), vec![1, 3]);
+        assert_eq!(pids("nginx", true), vec![1]);
+        assert!(pids("sshd", false).is_empty());
+    }
+
+    #[test]
+    fn test_search_command_globs() {
+        use crate::parser::{Command, CommandParser};
+
+        let processes: Vec<ProcessMetrics> = ["nginx", "bash", "nginx: worker", "php-fpm: worker"]
+            .iter()
+            .enumerate()
+            .map(|(i, comm)| ProcessMetrics { pid: i as u32 + 1, comm: comm.to_string(), ..Default::default() })
+            .collect();
+        // What `search` prints for a command line, as PIDs
+        let run = |line: &str| -> Vec<u32> {
+            let Command::SearchProcess { name, exact, .. } = CommandParser::new().parse(line).command else {
+                panic!("Expected SearchProcess command");
+            };
+            search(&processes, &name, exact).iter().map(|metrics| metrics.pid).collect()
+        };
+
+        assert_eq!(run("search nginx*"), vec![1, 3]);
+        assert_eq!(run("search *worker"), vec![3, 4]);
+        assert_eq!(run("search NGINX?"), Vec::<u32>::new());
+        assert_eq!(run("search ngin?"), vec![1]);
+        // A glob matches the whole name, so this is not a substring search
+        assert!(run("search work*").is_empty());
+        assert_eq!(run("search -e Nginx*"), Vec::<u32>::new());
+        assert_eq!(run("search --exact nginx*"), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("nginx*", "nginx"));
+        assert!(glob_match("nginx*", "nginx: worker"));
+        assert!(!glob_match("nginx*", "my-nginx"));
+        assert!(glob_match("*worker*", "kworker/0:1"));
+        assert!(glob_match("kworker/?:1", "kworker/3:1"));
+        assert!(!glob_match("kworker/?:1", "kworker/12:1"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXXbYYbc"));
+    }
+
+    #[test]
+    fn test_matches_name_with_glob() {
+        assert!(matches_name("Web Content", "web*", false));
+        assert!(!matches_name("Web Content", "web*", true));
+        // Globs anchor to the whole name, unlike the substring match
+        assert!(!matches_name("systemd-journald", "journal*", false));
+    }
+}
@@ -0,0 +1,470 @@
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+
+use crate::proc_reader::{io_accounting, DetailedInfo, ProcessMetrics};
+
+// How `ps`, `info` and `monitor` print processes: the aligned table for people,
+// or JSON/CSV for scripts. Pretty JSON is indented for reading; compact JSON
+// keeps each record on one line for piping.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json { pretty: bool },
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<OutputFormat> {
+        match name.to_lowercase().as_str() {
+            "table" => Some(OutputFormat::Table),
+            "json" => Some(OutputFormat::Json { pretty: false }),
+            "csv" => Some(OutputFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T, pretty: bool) -> String {
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    json.expect("process metrics serialize to JSON")
+}
+
+// An array of objects keyed by the ProcessMetrics field names
+pub fn processes_json(processes: &[ProcessMetrics], pretty: bool) -> String {
+    to_json(processes, pretty)
+}
+
+// `info` as JSON: the same object as one `ps` entry, plus the `-d` fields when read
+#[derive(Serialize)]
+struct ProcessInfoJson<'a> {
+    #[serde(flatten)]
+    metrics: &'a ProcessMetrics,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cmdline: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threads: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vm_size: Option<u64>,
+}
+
+pub fn process_json(metrics: &ProcessMetrics, detail: Option<&DetailedInfo>, pretty: bool) -> String {
+    let info = ProcessInfoJson {
+        metrics,
+        cmdline: detail.map(|detail| detail.cmdline.as_slice()),
+        threads: detail.map(|detail| detail.threads),
+        vm_size: detail.map(|detail| detail.vm_size),
+    };
+    to_json(&info, pretty)
+}
+
+// Columns of `process_csv_row`, named like the ProcessMetrics fields and the JSON keys
+pub const PROCESS_CSV_HEADER: &str = "pid,comm,user,uid,cpu_time,mem_usage,mem_percent,\
+io_read_bytes,io_write_bytes,io_denied,cpu_seconds,pss,nice,elapsed_seconds,last_cpu,\
+voluntary_ctxt_switches,nonvoluntary_ctxt_switches,state,ppid";
+
+// Every field of a process; missing optional values are left empty
+pub fn process_csv_row(metrics: &ProcessMetrics) -> String {
+    let optional = |value: Option<u64>| value.map_or_else(String::new, |value| value.to_string());
+    [
+        metrics.pid.to_string(),
+        csv_field(&metrics.comm),
+        csv_field(&metrics.user),
+        optional(metrics.uid.map(u64::from)),
+        metrics.cpu_time.to_string(),
+        metrics.mem_usage.to_string(),
+        metrics.mem_percent.to_string(),
+        metrics.io_read_bytes.to_string(),
+        metrics.io_write_bytes.to_string(),
+        metrics.io_denied.to_string(),
+        metrics.cpu_seconds.to_string(),
+        optional(metrics.pss),
+        metrics.nice.to_string(),
+        metrics.elapsed_seconds.to_string(),
+        optional(metrics.last_cpu.map(u64::from)),
+        metrics.voluntary_ctxt_switches.to_string(),
+        metrics.nonvoluntary_ctxt_switches.to_string(),
+        metrics.state.to_string(),
+        metrics.ppid.to_string(),
+    ]
+    .join(",")
+}
+
+// Header line matching the columns produced by `process_row`
+pub fn process_header() -> String {
+    format!(
+        "{:<8} {:<15} {:<10} {:<5} {:<10} {:<15} {:<15}",
+        "PID", "Process", "User", "State", "%CPU", "Memory(KB)", "Read/Write (bytes)"
+    )
+}
+
+// One aligned table row for a process
+pub fn process_row(metrics: &ProcessMetrics) -> String {
+    format!(
+        "{:<8} {:<15} {:<10} {:<5} {:<10.2} {:<15} {}",
+        metrics.pid,
+        metrics.comm,
+        metrics.user,
+        metrics.state,
+        metrics.cpu_time, // Here, cpu_time is %CPU
+        metrics.mem_usage,
+        io_column(metrics, io_accounting())
+    )
+}
+
+// "read/write" bytes, N/A on a kernel without per-process I/O accounting,
+// or "-" for a process whose io file we may not read
+fn io_column(metrics: &ProcessMetrics, available: bool) -> String {
+    if !available {
+        "N/A".to_string()
+    } else if metrics.io_denied {
+        "-".to_string()
+    } else {
+        format!("{:<7}/{}", metrics.io_read_bytes, metrics.io_write_bytes)
+    }
+}
+
+// Header for `process_row_both`, which puts absolute and relative usage in one column
+pub fn process_header_both() -> String {
+    format!(
+        "{:<8} {:<15} {:<10} {:<5} {:<16} {:<20} {:<15}",
+        "PID", "Process", "User", "State", "CPU", "Memory", "Read/Write (bytes)"
+    )
+}
+
+// Like `process_row`, but CPU shows total CPU time and memory shows RSS, each
+// followed by its percentage
+pub fn process_row_both(metrics: &ProcessMetrics) -> String {
+    format!(
+        "{:<8} {:<15} {:<10} {:<5} {:<16} {:<20} {}",
+        metrics.pid,
+        metrics.comm,
+        metrics.user,
+        metrics.state,
+        with_percent(&format!("{:.1}s", metrics.cpu_seconds), metrics.cpu_time),
+        with_percent(&format_kb(metrics.mem_usage), metrics.mem_percent),
+        io_column(metrics, io_accounting())
+    )
+}
+
+// "512.0 MB (6.3%)"
+pub fn with_percent(absolute: &str, percent: f64) -> String {
+    format!("{} ({:.1}%)", absolute, percent)
+}
+
+// Quote a CSV field when it contains a separator, quote or newline
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Format a size given in KB using the largest unit that keeps the value >= 1
+pub fn format_kb(kb: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    let mut value = kb as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", kb, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// Render an instant with a strftime pattern, in local time unless `utc` is set.
+// Logs from several machines line up when they're all recorded in UTC.
+pub fn format_timestamp(instant: DateTime<Utc>, utc: bool, pattern: &str) -> String {
+    if utc {
+        instant.format(pattern).to_string()
+    } else {
+        instant.with_timezone(&Local).format(pattern).to_string()
+    }
+}
+
+// When something started `elapsed_seconds` ago, rendered like `format_timestamp`
+pub fn started_at(elapsed_seconds: f64, utc: bool, pattern: &str) -> String {
+    let elapsed = chrono::Duration::milliseconds((elapsed_seconds * 1000.0) as i64);
+    format_timestamp(Utc::now() - elapsed, utc, pattern)
+}
+
+// The current time rendered like `format_timestamp`
+pub fn timestamp_now(utc: bool, pattern: &str) -> String {
+    format_timestamp(Utc::now(), utc, pattern)
+}
+
+// The whole of `stats` on one line for status bars, e.g.
+// "cpu=12.5% mem=40.2% swap=0.0% load=0.52,0.40,0.35". The keys and their
+// order are stable so scripts can split on spaces and `=`.
+pub fn summary_line(cpu: f64, mem_percent: f64, swap_percent: f64, load: [f64; 3]) -> String {
+    format!(
+        "cpu={:.1}% mem={:.1}% swap={:.1}% load={:.2},{:.2},{:.2}",
+        cpu, mem_percent, swap_percent, load[0], load[1], load[2]
+    )
+}
+
+// "4194304" -> "4,194,304"
+pub fn with_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+// Render values as a row of block characters scaled against `max`
+pub fn sparkline<'a, I>(values: I, max: f64) -> String
+where
+    I: IntoIterator<Item = &'a f64>,
+{
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    values
+        .into_iter()
+        .map(|&value| {
+            let ratio = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+            BARS[(ratio * (BARS.len() - 1) as f64).round() as usize]
+        })
+        .collect()
+}
+
+// Split `width` cells between the values in proportion to their size. Cells lost
+// to rounding go to the largest remainders so the segments always fill the width.
+pub fn bar_segments(values: &[u64], width: usize) -> Vec<usize> {
+    let total: u64 = values.iter().sum();
+    if total == 0 {
+        return vec![0; values.len()];
+    }
+
+    let exact: Vec<f64> = values
+        .iter()
+        .map(|&value| value as f64 / total as f64 * width as f64)
+        .collect();
+    let mut cells: Vec<usize> = exact.iter().map(|share| share.floor() as usize).collect();
+
+    let mut leftover = width - cells.iter().sum::<usize>();
+    let mut by_remainder: Vec<usize> = (0..values.len()).collect();
+    by_remainder.sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+    for i in by_remainder {
+        if leftover == 0 {
+            break;
+        }
+        cells[i] += 1;
+        leftover -= 1;
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_format_timestamp_utc_vs_local() {
+        use chrono::{NaiveDateTime, Offset, TimeZone};
+
+        const PATTERN: &str = "%Y-%m-%d %H:%M:%S";
+        let instant = Utc.with_ymd_and_hms(2024, 3, 15, 12, 30, 0).unwrap();
+        assert_eq!(format_timestamp(instant, true, PATTERN), "2024-03-15 12:30:00");
+
+        let parse = |text: String| NaiveDateTime::parse_from_str(&text, PATTERN).unwrap();
+        let local = parse(format_timestamp(instant, false, PATTERN));
+        let utc = parse(format_timestamp(instant, true, PATTERN));
+        let offset = Local.offset_from_utc_datetime(&instant.naive_utc()).fix();
+        assert_eq!((local - utc).num_seconds(), offset.local_minus_utc() as i64);
+    }
+
+    #[test]
+    fn test_io_column() {
+        let metrics = ProcessMetrics { io_read_bytes: 4096, io_write_bytes: 12, ..Default::default() };
+        assert_eq!(io_column(&metrics, true), "4096   /12");
+        assert_eq!(io_column(&metrics, false), "N/A");
+
+        let denied = ProcessMetrics { io_denied: true, ..Default::default() };
+        assert_eq!(io_column(&denied, true), "-");
+        assert_eq!(io_column(&denied, false), "N/A");
+    }
+
+    #[test]
+    fn test_summary_line() {
+        assert_eq!(
+            summary_line(12.34, 40.0, 0.0, [0.5, 0.404, 1.0]),
+            "cpu=12.3% mem=40.0% swap=0.0% load=0.50,0.40,1.00"
+        );
+        assert!(!summary_line(100.0, 99.9, 50.0, [12.0, 8.0, 4.0]).contains('\n'));
+    }
+
+    #[test]
+    fn test_with_thousands() {
+        assert_eq!(with_thousands(0), "0");
+        assert_eq!(with_thousands(999), "999");
+        assert_eq!(with_thousands(12043), "12,043");
+        assert_eq!(with_thousands(4194304), "4,194,304");
+    }
+
+    #[test]
+    fn test_format_kb() {
+        assert_eq!(format_kb(512), "512 KB");
+        assert_eq!(format_kb(1536), "1.5 MB");
+        assert_eq!(format_kb(3 * 1024 * 1024), "3.0 GB");
+    }
+
+    #[test]
+    fn test_combined_usage_format() {
+        assert_eq!(with_percent(&format_kb(512 * 1024), 6.25), "512.0 MB (6.2%)");
+
+        let metrics = ProcessMetrics {
+            pid: 7,
+            comm: "nginx".to_string(),
+            user: "www".to_string(),
+            cpu_time: 4.0,
+            cpu_seconds: 12.34,
+            mem_usage: 2048,
+            mem_percent: 0.5,
+            ..Default::default()
+        };
+        let row = process_row_both(&metrics);
+        assert!(row.contains("12.3s (4.0%)"));
+        assert!(row.contains("2.0 MB (0.5%)"));
+    }
+
+    #[test]
+    fn test_state_column() {
+        let metrics = ProcessMetrics {
+            pid: 7,
+            comm: "defunct".to_string(),
+            user: "www".to_string(),
+            state: 'Z',
+            ..Default::default()
+        };
+        let state_at = process_header().find("State").unwrap();
+        assert_eq!(process_row(&metrics).chars().nth(state_at), Some('Z'));
+        let state_at = process_header_both().find("State").unwrap();
+        assert_eq!(process_row_both(&metrics).chars().nth(state_at), Some('Z'));
+    }
+
+    fn sample_metrics() -> ProcessMetrics {
+        ProcessMetrics {
+            pid: 812,
+            comm: "sshd, main".to_string(),
+            user: "root".to_string(),
+            uid: Some(0),
+            cpu_time: 1.5,
+            mem_usage: 2048,
+            state: 'S',
+            ppid: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_process_csv_row() {
+        let row = process_csv_row(&sample_metrics());
+        // The quoted comm is the only field with a comma in it
+        let (comm_start, comm_end) = (row.find('"').unwrap(), row.rfind('"').unwrap());
+        assert_eq!(&row[comm_start..=comm_end], "\"sshd, main\"");
+        let row = format!("{}comm{}", &row[..comm_start], &row[comm_end + 1..]);
+
+        let header: Vec<&str> = PROCESS_CSV_HEADER.split(',').collect();
+        let fields: HashMap<&str, &str> = header.iter().copied().zip(row.split(',')).collect();
+        assert_eq!(fields.len(), header.len());
+        assert_eq!(row.split(',').count(), header.len());
+        assert_eq!(fields["pid"], "812");
+        assert_eq!(fields["user"], "root");
+        assert_eq!(fields["uid"], "0");
+        assert_eq!(fields["cpu_time"], "1.5");
+        assert_eq!(fields["pss"], "");
+        assert_eq!(fields["state"], "S");
+        assert_eq!(fields["ppid"], "1");
+    }
+
+    #[test]
+    fn test_processes_json() {
+        let json: serde_json::Value = serde_json::from_str(&processes_json(&[sample_metrics()], false)).unwrap();
+        let process = &json[0];
+        assert_eq!(process["pid"], 812);
+        assert_eq!(process["comm"], "sshd, main");
+        assert_eq!(process["state"], "S");
+        assert!(process["pss"].is_null());
+        // The JSON keys and the CSV columns are the same set
+        let mut keys: Vec<&str> = process.as_object().unwrap().keys().map(String::as_str).collect();
+        let mut columns: Vec<&str> = PROCESS_CSV_HEADER.split(',').collect();
+        keys.sort_unstable();
+        columns.sort_unstable();
+        assert_eq!(keys, columns);
+    }
+
+    #[test]
+    fn test_pretty_json() {
+        let processes = [sample_metrics()];
+        let compact = processes_json(&processes, false);
+        let pretty = processes_json(&processes, true);
+        assert!(!compact.contains('\n'));
+        assert!(pretty.contains('\n'));
+        // Only the layout differs
+        let compact: serde_json::Value = serde_json::from_str(&compact).unwrap();
+        let pretty: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(compact, pretty);
+    }
+
+    #[test]
+    fn test_process_json() {
+        let metrics = sample_metrics();
+        let json: serde_json::Value = serde_json::from_str(&process_json(&metrics, None, false)).unwrap();
+        assert_eq!(json["pid"], 812);
+        assert!(json.get("cmdline").is_none());
+
+        let detail = DetailedInfo {
+            cmdline: vec!["sshd".to_string(), "-D".to_string()],
+            threads: 1,
+            vm_size: 4096,
+            state: 'S',
+            elapsed_seconds: 10.0,
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&process_json(&metrics, Some(&detail), true)).unwrap();
+        assert_eq!(json["comm"], "sshd, main");
+        assert_eq!(json["cmdline"][1], "-D");
+        assert_eq!(json["threads"], 1);
+        assert_eq!(json["vm_size"], 4096);
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("nginx"), "nginx");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_bar_segments() {
+        assert_eq!(bar_segments(&[50, 25, 25], 20), vec![10, 5, 5]);
+        // 1/3 each of 10 cells: the rounding leftover still gets handed out
+        assert_eq!(bar_segments(&[1, 1, 1], 10).iter().sum::<usize>(), 10);
+        assert_eq!(bar_segments(&[600, 300, 100, 0], 10), vec![6, 3, 1, 0]);
+        assert_eq!(bar_segments(&[0, 0], 10), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let series = [0.0, 25.0, 50.0, 75.0, 100.0, 150.0];
+        assert_eq!(sparkline(&series, 100.0), "▁▃▅▆██");
+        assert_eq!(sparkline(&[], 100.0), "");
+    }
+}
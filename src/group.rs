@@ -0,0 +1,213 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::proc_reader::ProcessMetrics;
+
+// Count processes per key, largest groups first (ties broken by key)
+fn group_counts<F>(processes: &[ProcessMetrics], key: F) -> Vec<(String, usize)>
+where
+    F: Fn(&ProcessMetrics) -> &str,
+{
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for metrics in processes {
+        *counts.entry(key(metrics)).or_insert(0) += 1;
+    }
+
+    let mut groups: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(key, count)| (key.to_string(), count))
+        .collect();
+    groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+pub fn group_by_user(processes: &[ProcessMetrics]) -> Vec<(String, usize)> {
+    group_counts(processes, |metrics| &metrics.user)
+}
+
+pub fn group_by_name(processes: &[ProcessMetrics]) -> Vec<(String, usize)> {
+    group_counts(processes, |metrics| &metrics.comm)
+}
+
+// The processes `byname` counts under `comm`, in scan (PID) order
+pub fn same_name<'a>(processes: &'a [ProcessMetrics], comm: &str) -> Vec<&'a ProcessMetrics> {
+    processes.iter().filter(|metrics| metrics.comm == comm).collect()
+}
+
+// Lowest, mean and highest value of one measure across a group
+#[derive(Debug, PartialEq)]
+pub struct Spread {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+pub fn spread<I>(values: I) -> Option<Spread>
+where
+    I: IntoIterator<Item = f64>,
+{
+    let mut count = 0;
+    let mut total = 0.0;
+    let mut min = f64::INFINITY;
+    let mut max = f64::NEG_INFINITY;
+    for value in values {
+        count += 1;
+        total += value;
+        min = min.min(value);
+        max = max.max(value);
+    }
+    (count > 0).then(|| Spread { min, avg: total / count as f64, max })
+}
+
+// Each process's cumulative CPU time as a percentage of the CPU time used by
+// all processes, largest share first
+pub fn cpu_shares(processes: &[ProcessMetrics]) -> Vec<(&ProcessMetrics, f64)> {
+    let total: f64 = processes.iter().map(|metrics| metrics.cpu_seconds).sum();
+    let mut shares: Vec<(&ProcessMetrics, f64)> = processes
+        .iter()
+        .map(|metrics| {
+            let share = if total > 0.0 {
+                metrics.cpu_seconds / total * 100.0
+            } else {
+                0.0
+            };
+            (metrics, share)
+        })
+        .collect();
+    shares.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.pid.cmp(&b.0.pid)));
+    shares
+}
+
+// Processes with pages in swap paired with their swap usage in KB, most
+// swapped first. Entries with no swap are dropped.
+pub fn swapped_processes(usage: &[(ProcessMetrics, u64)]) -> Vec<&(ProcessMetrics, u64)> {
+    let mut swapped: Vec<&(ProcessMetrics, u64)> = usage.iter().filter(|(_, swap)| *swap > 0).collect();
+    swapped.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.pid.cmp(&b.0.pid)));
+    swapped
+}
+
+// Number of processes at each nice level, from highest priority (-20) to lowest
+pub fn nice_distribution(processes: &[ProcessMetrics]) -> Vec<(i64, usize)> {
+    let mut levels: BTreeMap<i64, usize> = BTreeMap::new();
+    for metrics in processes {
+        *levels.entry(metrics.nice).or_insert(0) += 1;
+    }
+    levels.into_iter().collect()
+}
+
+// Upper bounds (in seconds) of the `agedist` buckets; anything older falls in the last one
+const AGE_BUCKETS: [(&str, f64); 3] = [("< 1m", 60.0), ("1m - 1h", 3600.0), ("1h - 1d", 86400.0)];
+
+// Number of processes per age bucket, youngest first
+pub fn age_distribution(processes: &[ProcessMetrics]) -> Vec<(&'static str, usize)> {
+    let mut buckets: Vec<(&'static str, usize)> =
+        AGE_BUCKETS.iter().map(|(label, _)| (*label, 0)).collect();
+    buckets.push(("> 1d", 0));
+
+    for metrics in processes {
+        let index = AGE_BUCKETS
+            .iter()
+            .position(|(_, limit)| metrics.elapsed_seconds < *limit)
+            .unwrap_or(AGE_BUCKETS.len());
+        buckets[index].1 += 1;
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(comm: &str, user: &str) -> ProcessMetrics {
+        ProcessMetrics {
+            comm: comm.to_string(),
+            user: user.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_group_by_user_and_name() {
+        let processes = vec![
+            process("nginx", "www-data"),
+            process("nginx", "www-data"),
+            process("sshd", "root"),
+            process("nginx", "root"),
+        ];
+
+        let by_user = group_by_user(&processes);
+        assert_eq!(by_user, vec![("root".to_string(), 2), ("www-data".to_string(), 2)]);
+
+        let by_name = group_by_name(&processes);
+        assert_eq!(by_name, vec![("nginx".to_string(), 3), ("sshd".to_string(), 1)]);
+        // `--count` reports the number of groups
+        assert_eq!(by_name.len(), 2);
+    }
+
+    #[test]
+    fn test_same_name_and_spread() {
+        let processes = vec![
+            ProcessMetrics { pid: 10, comm: "php-fpm".to_string(), cpu_time: 2.0, ..Default::default() },
+            ProcessMetrics { pid: 11, comm: "nginx".to_string(), cpu_time: 50.0, ..Default::default() },
+            ProcessMetrics { pid: 12, comm: "php-fpm".to_string(), cpu_time: 94.0, ..Default::default() },
+            ProcessMetrics { pid: 13, comm: "php-fpm".to_string(), cpu_time: 4.0, ..Default::default() },
+        ];
+
+        let peers = same_name(&processes, "php-fpm");
+        assert_eq!(peers.iter().map(|m| m.pid).collect::<Vec<_>>(), vec![10, 12, 13]);
+        assert_eq!(
+            spread(peers.iter().map(|m| m.cpu_time)),
+            Some(Spread { min: 2.0, avg: 100.0 / 3.0, max: 94.0 })
+        );
+        assert_eq!(spread(Vec::new()), None);
+    }
+
+    #[test]
+    fn test_cpu_shares() {
+        let processes = vec![
+            ProcessMetrics { pid: 1, cpu_seconds: 10.0, ..Default::default() },
+            ProcessMetrics { pid: 2, cpu_seconds: 30.0, ..Default::default() },
+            ProcessMetrics { pid: 3, cpu_seconds: 60.0, ..Default::default() },
+        ];
+
+        let shares: Vec<(u32, f64)> =
+            cpu_shares(&processes).iter().map(|(m, share)| (m.pid, *share)).collect();
+        assert_eq!(shares, vec![(3, 60.0), (2, 30.0), (1, 10.0)]);
+    }
+
+    #[test]
+    fn test_swapped_processes() {
+        let usage: Vec<(ProcessMetrics, u64)> = [(1, 0), (2, 512), (3, 4096), (4, 512)]
+            .iter()
+            .map(|&(pid, swap)| (ProcessMetrics { pid, ..Default::default() }, swap))
+            .collect();
+
+        let swapped: Vec<(u32, u64)> =
+            swapped_processes(&usage).iter().map(|(m, swap)| (m.pid, *swap)).collect();
+        assert_eq!(swapped, vec![(3, 4096), (2, 512), (4, 512)]);
+    }
+
+    #[test]
+    fn test_nice_distribution() {
+        let processes = vec![
+            ProcessMetrics { nice: 0, ..Default::default() },
+            ProcessMetrics { nice: 19, ..Default::default() },
+            ProcessMetrics { nice: -5, ..Default::default() },
+            ProcessMetrics { nice: 0, ..Default::default() },
+        ];
+
+        assert_eq!(nice_distribution(&processes), vec![(-5, 1), (0, 2), (19, 1)]);
+    }
+
+    #[test]
+    fn test_age_distribution() {
+        let processes: Vec<ProcessMetrics> = [5.0, 59.9, 60.0, 7200.0, 86400.0, 1e7]
+            .iter()
+            .map(|&elapsed_seconds| ProcessMetrics { elapsed_seconds, ..Default::default() })
+            .collect();
+
+        assert_eq!(
+            age_distribution(&processes),
+            vec![("< 1m", 2), ("1m - 1h", 1), ("1h - 1d", 1), ("> 1d", 2)]
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+use crate::filter::matches_name;
+
+// Set by the SIGINT handler while a looping command is running
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_sigint(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+// While alive, Ctrl-C stops the current loop instead of killing the program.
+// Dropping it puts back the previous SIGINT behaviour and restores the terminal,
+// so the prompt and the one-shot commands keep the normal Ctrl-C semantics.
+pub struct InterruptGuard {
+    previous: Option<SigAction>,
+}
+
+pub fn catch_interrupts() -> InterruptGuard {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    let action = SigAction::new(
+        SigHandler::Handler(handle_sigint),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    let previous = unsafe { sigaction(Signal::SIGINT, &action) }.ok();
+    InterruptGuard { previous }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        let previous = self
+            .previous
+            .unwrap_or_else(|| SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty()));
+        // SAFETY: restoring the disposition that was in place before the guard
+        let _ = unsafe { sigaction(Signal::SIGINT, &previous) };
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        restore_terminal();
+    }
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+// Leave raw mode and make the cursor visible again after a live view
+pub fn restore_terminal() {
+    let _ = terminal::disable_raw_mode();
+    if io::stdout().is_terminal() {
+        print!("\x1B[?25h");
+        let _ = io::stdout().flush();
+    }
+}
+
+// Why `wait_for_key` returned
+#[derive(Debug, PartialEq)]
+pub enum Wait {
+    Elapsed,
+    Quit,
+    Key(KeyCode),
+}
+
+// Wait until `deadline` or the next key press, whichever comes first. `q` and
+// Ctrl-C (a key in raw mode, SIGINT otherwise) mean Quit; while `typing`, only
+// Ctrl-C does, so `q` can be typed like any other character.
+pub fn wait_for_key(deadline: Instant, typing: bool) -> Wait {
+    if interrupted() {
+        return Wait::Quit;
+    }
+
+    // Without a terminal there is no keyboard to watch, only SIGINT
+    if !io::stdin().is_terminal() || terminal::enable_raw_mode().is_err() {
+        while !interrupted() && Instant::now() < deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            thread::sleep(remaining.min(Duration::from_millis(100)));
+        }
+        return if interrupted() { Wait::Quit } else { Wait::Elapsed };
+    }
+
+    let outcome = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break Wait::Elapsed;
+        }
+        match event::poll(remaining) {
+            Ok(true) => {
+                if let Ok(Event::Key(key)) = event::read()
+                    && key.kind == KeyEventKind::Press
+                {
+                    let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                    if ctrl_c || (!typing && is_quit_key(key.code, key.modifiers)) {
+                        break Wait::Quit;
+                    }
+                    break Wait::Key(key.code);
+                }
+            }
+            Ok(false) => break Wait::Elapsed,
+            Err(_) => {
+                thread::sleep(remaining);
+                break Wait::Elapsed;
+            }
+        }
+    };
+
+    let _ = terminal::disable_raw_mode();
+    outcome
+}
+
+// Sleep for one refresh interval while watching the keyboard. Returns true if
+// the user pressed `q` or Ctrl-C to stop; other keys are ignored.
+// Whatever the last refresh printed is flushed first, so a consumer reading
+// through a pipe gets each refresh as it happens rather than at exit.
+pub fn wait_for_quit(interval: Duration) -> bool {
+    let _ = io::stdout().flush();
+    let deadline = Instant::now() + interval;
+    loop {
+        match wait_for_key(deadline, false) {
+            Wait::Elapsed => return false,
+            Wait::Quit => return true,
+            Wait::Key(_) => {}
+        }
+    }
+}
+
+// Drive a live view: run `refresh` once per interval until it returns false,
+// the user quits, or `count` refreshes have been shown (forever if None)
+pub fn run_refresh_loop<F>(interval: Duration, count: Option<u64>, refresh: F)
+where
+    F: FnMut() -> bool,
+{
+    run_refresh_loop_with(count, refresh, || wait_for_quit(interval));
+}
+
+// Like `run_refresh_loop`, but with the view's own `wait` between refreshes,
+// for views that vary their interval or react to keys; it returns true to stop
+pub fn run_refresh_loop_with<F, W>(count: Option<u64>, refresh: F, wait: W)
+where
+    F: FnMut() -> bool,
+    W: FnMut() -> bool,
+{
+    let _interrupts = catch_interrupts();
+    refresh_loop(count, refresh, wait);
+}
+
+// The incremental name filter of a live view, like htop's: `/` starts typing
+// a filter, Enter keeps it, Backspace edits it and Esc clears it
+#[derive(Debug, Default)]
+pub struct LiveFilter {
+    pub text: String,
+    pub typing: bool,
+}
+
+impl LiveFilter {
+    // Apply one key press; true when the view needs redrawing
+    pub fn handle(&mut self, code: KeyCode) -> bool {
+        match (self.typing, code) {
+            (_, KeyCode::Esc) => {
+                let changed = self.typing || !self.text.is_empty();
+                self.typing = false;
+                self.text.clear();
+                changed
+            }
+            (false, KeyCode::Char('/')) | (true, KeyCode::Enter) => {
+                self.typing = !self.typing;
+                true
+            }
+            (true, KeyCode::Char(c)) => {
+                self.text.push(c);
+                true
+            }
+            (true, KeyCode::Backspace) => self.text.pop().is_some(),
+            _ => false,
+        }
+    }
+
+    pub fn allows(&self, comm: &str) -> bool {
+        self.text.is_empty() || matches_name(comm, &self.text, false)
+    }
+
+    // Shown under the table while a filter is being typed or applied
+    pub fn status(&self) -> Option<String> {
+        if self.typing {
+            Some(format!("Filter: {}_", self.text))
+        } else if !self.text.is_empty() {
+            Some(format!("Filter: {} (Esc to clear)", self.text))
+        } else {
+            None
+        }
+    }
+}
+
+// Refresh interval for `monitor --adaptive`: `min` seconds on an idle system,
+// stretching linearly to `max` as global CPU usage reaches 100%, so the
+// monitor backs off instead of adding to the load it's measuring
+pub fn adaptive_interval(cpu_percent: f32, min: u64, max: u64) -> Duration {
+    // A NaN reading (no baseline yet) counts as idle
+    let cpu_percent = if cpu_percent.is_nan() { 0.0 } else { cpu_percent.clamp(0.0, 100.0) };
+    let busy = f64::from(cpu_percent) / 100.0;
+    let span = max.saturating_sub(min) as f64;
+    Duration::from_secs_f64(min as f64 + span * busy)
+}
+
+// Loop logic behind `run_refresh_loop`; `wait` returns true when it's time to stop
+fn refresh_loop<F, W>(count: Option<u64>, mut refresh: F, mut wait: W)
+where
+    F: FnMut() -> bool,
+    W: FnMut() -> bool,
+{
+    let mut done = 0;
+    loop {
+        if !refresh() {
+            break;
+        }
+        done += 1;
+        if count.is_some_and(|count| done >= count) || wait() {
+            break;
+        }
+    }
+}
+
+// Spaces out a burst of actions, such as signalling hundreds of processes, to
+// at most `rate` per second so they don't spike the load all at once
+pub struct Pacer {
+    spacing: Option<Duration>,
+    next: Option<Instant>,
+}
+
+impl Pacer {
+    // No limit when `rate` is None
+    pub fn new(rate: Option<u32>) -> Pacer {
+        let spacing = rate.map(|rate| Duration::from_secs(1) / rate.max(1));
+        Pacer { spacing, next: None }
+    }
+
+    // Block until the next action is allowed
+    pub fn wait(&mut self) {
+        let delay = self.delay_at(Instant::now());
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+
+    // How long an action at `now` must wait, reserving its slot
+    fn delay_at(&mut self, now: Instant) -> Duration {
+        let Some(spacing) = self.spacing else {
+            return Duration::ZERO;
+        };
+        let slot = self.next.map_or(now, |next| next.max(now));
+        self.next = Some(slot + spacing);
+        slot - now
+    }
+}
+
+fn is_quit_key(code: KeyCode, modifiers: KeyModifiers) -> bool {
+    match code {
+        KeyCode::Char('q') | KeyCode::Char('Q') => true,
+        KeyCode::Char('c') => modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interrupt_ends_only_the_current_view() {
+        let guard = catch_interrupts();
+        nix::sys::signal::raise(Signal::SIGINT).unwrap();
+        assert!(interrupted());
+
+        // The loop stops at its next wait instead of the program exiting
+        let mut refreshes = 0;
+        refresh_loop(
+            None,
+            || {
+                refreshes += 1;
+                true
+            },
+            interrupted,
+        );
+        assert_eq!(refreshes, 1);
+
+        // The next live view starts with the flag cleared
+        drop(guard);
+        assert!(!interrupted());
+        let _guard = catch_interrupts();
+        assert!(!interrupted());
+    }
+
+    #[test]
+    fn test_adaptive_interval() {
+        assert_eq!(adaptive_interval(0.0, 1, 10), Duration::from_secs(1));
+        assert_eq!(adaptive_interval(100.0, 1, 10), Duration::from_secs(10));
+        assert_eq!(adaptive_interval(50.0, 2, 12), Duration::from_secs(7));
+        // Out-of-range readings stay within the bounds
+        assert_eq!(adaptive_interval(250.0, 1, 10), Duration::from_secs(10));
+        assert_eq!(adaptive_interval(f32::NAN, 1, 10).as_secs(), 1);
+        assert_eq!(adaptive_interval(80.0, 4, 4), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_pacer_spaces_out_actions() {
+        let start = Instant::now();
+        let mut pacer = Pacer::new(Some(4));
+        assert_eq!(pacer.delay_at(start), Duration::ZERO);
+        assert_eq!(pacer.delay_at(start), Duration::from_millis(250));
+        assert_eq!(pacer.delay_at(start + Duration::from_millis(100)), Duration::from_millis(400));
+        // After a pause, the next action goes straight away
+        assert_eq!(pacer.delay_at(start + Duration::from_secs(5)), Duration::ZERO);
+
+        let mut unlimited = Pacer::new(None);
+        assert_eq!(unlimited.delay_at(start), Duration::ZERO);
+        assert_eq!(unlimited.delay_at(start), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_live_filter_keys() {
+        let mut filter = LiveFilter::default();
+        // Typing only starts with `/`
+        assert!(!filter.handle(KeyCode::Char('n')));
+        assert!(filter.handle(KeyCode::Char('/')));
+        for c in "ngx".chars() {
+            assert!(filter.handle(KeyCode::Char(c)));
+        }
+        assert!(filter.handle(KeyCode::Backspace));
+        assert_eq!(filter.status(), Some("Filter: ng_".to_string()));
+        assert!(filter.allows("Nginx"));
+        assert!(!filter.allows("sshd"));
+
+        assert!(filter.handle(KeyCode::Enter));
+        assert!(!filter.typing);
+        assert_eq!(filter.status(), Some("Filter: ng (Esc to clear)".to_string()));
+
+        assert!(filter.handle(KeyCode::Esc));
+        assert_eq!(filter.status(), None);
+        assert!(filter.allows("sshd"));
+        assert!(!filter.handle(KeyCode::Esc));
+    }
+
+    #[test]
+    fn test_refresh_loop_stops_after_count() {
+        let mut refreshes = 0;
+        let mut waits = 0;
+        refresh_loop(
+            Some(3),
+            || {
+                refreshes += 1;
+                true
+            },
+            || {
+                waits += 1;
+                false
+            },
+        );
+        assert_eq!(refreshes, 3);
+        // No pointless wait after the final refresh
+        assert_eq!(waits, 2);
+    }
+
+    #[test]
+    fn test_refresh_loop_stops_when_refresh_or_wait_ends_it() {
+        let mut refreshes = 0;
+        refresh_loop(
+            None,
+            || {
+                refreshes += 1;
+                refreshes < 4
+            },
+            || false,
+        );
+        assert_eq!(refreshes, 4);
+
+        let mut refreshes = 0;
+        refresh_loop(
+            None,
+            || {
+                refreshes += 1;
+                true
+            },
+            || true,
+        );
+        assert_eq!(refreshes, 1);
+    }
+}
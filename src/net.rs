@@ -0,0 +1,134 @@
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+// One socket from /proc/net/{tcp,tcp6,udp,udp6}
+#[derive(Debug, PartialEq)]
+pub struct Connection {
+    pub proto: &'static str,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub state: &'static str,
+    pub inode: u64,
+}
+
+// Socket tables to read, with whether their addresses are IPv6
+const TABLES: [(&str, &str, bool); 4] = [
+    ("/proc/net/tcp", "tcp", false),
+    ("/proc/net/tcp6", "tcp6", true),
+    ("/proc/net/udp", "udp", false),
+    ("/proc/net/udp6", "udp6", true),
+];
+
+// TCP states from include/net/tcp_states.h. UDP reuses the numbering: a
+// connected UDP socket is ESTABLISHED, an unconnected one CLOSE.
+fn state_name(proto: &str, code: u8) -> &'static str {
+    match (code, proto.starts_with("udp")) {
+        (0x01, true) => "ESTAB",
+        (0x07, true) => "UNCONN",
+        (0x01, false) => "ESTABLISHED",
+        (0x02, false) => "SYN_SENT",
+        (0x03, false) => "SYN_RECV",
+        (0x04, false) => "FIN_WAIT1",
+        (0x05, false) => "FIN_WAIT2",
+        (0x06, false) => "TIME_WAIT",
+        (0x07, false) => "CLOSE",
+        (0x08, false) => "CLOSE_WAIT",
+        (0x09, false) => "LAST_ACK",
+        (0x0A, false) => "LISTEN",
+        (0x0B, false) => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+// Decode `ADDR:PORT` as the kernel prints it: the port is big-endian hex, but
+// the address is the raw in-memory bytes, printed as 32-bit words in host
+// (little-endian on x86/ARM) order
+pub fn parse_hex_addr(text: &str, ipv6: bool) -> Option<SocketAddr> {
+    let (addr, port) = text.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let mut bytes = Vec::with_capacity(16);
+    for i in (0..addr.len()).step_by(8) {
+        let word = u32::from_str_radix(addr.get(i..i + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let ip = if ipv6 {
+        let octets: [u8; 16] = bytes.try_into().ok()?;
+        IpAddr::V6(Ipv6Addr::from(octets))
+    } else {
+        let octets: [u8; 4] = bytes.try_into().ok()?;
+        IpAddr::V4(Ipv4Addr::from(octets))
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+// Parse one socket table; the first line is a header. Columns are
+// `sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode ...`
+pub fn parse_net_table(content: &str, proto: &'static str, ipv6: bool) -> Vec<Connection> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(Connection {
+                proto,
+                local: parse_hex_addr(fields.get(1)?, ipv6)?,
+                remote: parse_hex_addr(fields.get(2)?, ipv6)?,
+                state: state_name(proto, u8::from_str_radix(fields.get(3)?, 16).ok()?),
+                inode: fields.get(9)?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+// Every TCP and UDP socket on the system. A missing table (e.g. IPv6 disabled)
+// just contributes nothing.
+pub fn read_connections() -> Vec<Connection> {
+    TABLES
+        .iter()
+        .flat_map(|&(path, proto, ipv6)| {
+            fs::read_to_string(path)
+                .map(|content| parse_net_table(&content, proto, ipv6))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_addr() {
+        assert_eq!(
+            parse_hex_addr("0100007F:1F90", false),
+            Some("127.0.0.1:8080".parse().unwrap())
+        );
+        assert_eq!(parse_hex_addr("00000000:0016", false), Some("0.0.0.0:22".parse().unwrap()));
+        assert_eq!(
+            parse_hex_addr("00000000000000000000000001000000:0050", true),
+            Some("[::1]:80".parse().unwrap())
+        );
+        assert_eq!(parse_hex_addr("0100007F", false), None);
+        assert_eq!(parse_hex_addr("0100007F:1F90", true), None);
+    }
+
+    #[test]
+    fn test_parse_net_table() {
+        let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+            \x20  0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 15263 1 0000000000000000 100 0 0 10 0\n\
+            \x20  1: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 48812 1 0000000000000000 20 4 30 10 -1\n";
+
+        let connections = parse_net_table(content, "tcp", false);
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[0].local, "0.0.0.0:22".parse().unwrap());
+        assert_eq!(connections[0].state, "LISTEN");
+        assert_eq!(connections[0].inode, 15263);
+        assert_eq!(connections[1].remote, "127.0.0.1:50000".parse().unwrap());
+        assert_eq!(connections[1].state, "ESTABLISHED");
+
+        let udp = parse_net_table(content, "udp", false);
+        assert_eq!(udp[1].state, "ESTAB");
+        assert_eq!(udp[0].state, "UNKNOWN");
+    }
+}
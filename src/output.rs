@@ -0,0 +1,98 @@
+use std::io::{self, BufWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// When --format json|csv records reach stdout, set with the `buffer` command.
+// Line (the default) flushes every record as soon as it's written, so a
+// consumer reading a pipe sees each one at once; block lets records collect
+// and go out in large writes, which is cheaper for a consumer that only reads
+// the whole output at the end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Buffering {
+    Line,
+    Block,
+}
+
+impl Buffering {
+    pub fn parse(name: &str) -> Option<Buffering> {
+        match name.to_lowercase().as_str() {
+            "line" => Some(Buffering::Line),
+            "block" => Some(Buffering::Block),
+            _ => None,
+        }
+    }
+}
+
+static BLOCK_BUFFERED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_buffering(mode: Buffering) {
+    BLOCK_BUFFERED.store(mode == Buffering::Block, Ordering::SeqCst);
+}
+
+pub fn buffering() -> Buffering {
+    if BLOCK_BUFFERED.load(Ordering::SeqCst) {
+        Buffering::Block
+    } else {
+        Buffering::Line
+    }
+}
+
+// Writes records one per line. In line mode each record is flushed as it's
+// written; in block mode only when the buffer fills or the writer is dropped.
+pub struct RecordWriter<W: Write> {
+    out: BufWriter<W>,
+    mode: Buffering,
+}
+
+impl RecordWriter<io::Stdout> {
+    // Stdout in the mode currently set
+    pub fn stdout() -> Self {
+        RecordWriter::new(io::stdout(), buffering())
+    }
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(inner: W, mode: Buffering) -> Self {
+        RecordWriter { out: BufWriter::new(inner), mode }
+    }
+
+    // Fails once the reader has gone away (a closed pipe), so callers can stop
+    pub fn record(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.out, "{}", line)?;
+        if self.mode == Buffering::Line {
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_buffering() {
+        assert_eq!(Buffering::parse("line"), Some(Buffering::Line));
+        assert_eq!(Buffering::parse("BLOCK"), Some(Buffering::Block));
+        assert_eq!(Buffering::parse("full"), None);
+    }
+
+    #[test]
+    fn test_line_mode_emits_each_record() {
+        let mut writer = RecordWriter::new(Vec::new(), Buffering::Line);
+        writer.record("{\"pid\":1}").unwrap();
+        // Already with the reader, not held back until the writer finishes
+        assert_eq!(writer.out.get_ref().as_slice(), b"{\"pid\":1}\n");
+        writer.record("{\"pid\":2}").unwrap();
+        assert_eq!(writer.out.get_ref().as_slice(), b"{\"pid\":1}\n{\"pid\":2}\n");
+    }
+
+    #[test]
+    fn test_block_mode_holds_records() {
+        let mut writer = RecordWriter::new(Vec::new(), Buffering::Block);
+        writer.record("{\"pid\":1}").unwrap();
+        writer.record("{\"pid\":2}").unwrap();
+        assert!(writer.out.get_ref().is_empty());
+        let inner = writer.out.into_inner().unwrap();
+        assert_eq!(inner.as_slice(), b"{\"pid\":1}\n{\"pid\":2}\n");
+    }
+}
@@ -0,0 +1,70 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::output::RecordWriter;
+
+// Pager used when $PAGER isn't set, tried in order
+const FALLBACK_PAGERS: [&str; 2] = ["less", "more"];
+
+// Print output, going through a pager when asked for and stdout is a terminal.
+// Piped output is always written directly so scripts see plain text.
+pub fn emit(lines: &[String], use_pager: bool) {
+    if use_pager && io::stdout().is_terminal() && page(lines) {
+        return;
+    }
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+// Like `emit`, for --format json|csv records: unpaged, they're written in the
+// mode set with `buffer`, and writing stops if the reader goes away
+pub fn emit_records(lines: &[String], use_pager: bool) {
+    if use_pager && io::stdout().is_terminal() && page(lines) {
+        return;
+    }
+    let mut out = RecordWriter::stdout();
+    for line in lines {
+        if out.record(line).is_err() {
+            break;
+        }
+    }
+}
+
+// Pipe the lines through $PAGER (or a fallback); false if no pager could be run
+fn page(lines: &[String]) -> bool {
+    let mut candidates: Vec<String> = Vec::new();
+    if let Ok(pager) = env::var("PAGER")
+        && !pager.trim().is_empty()
+    {
+        candidates.push(pager);
+    }
+    candidates.extend(FALLBACK_PAGERS.iter().map(|p| p.to_string()));
+
+    for candidate in candidates {
+        let mut words = candidate.split_whitespace();
+        let Some(program) = words.next() else {
+            continue;
+        };
+        let Ok(mut child) = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .spawn()
+        else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            for line in lines {
+                // The user quitting the pager early closes the pipe; stop writing
+                if writeln!(stdin, "{}", line).is_err() {
+                    break;
+                }
+            }
+        }
+        let _ = child.wait();
+        return true;
+    }
+    false
+}
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::format::OutputFormat;
+use crate::output::Buffering;
+use crate::sort::Resource;
 
 #[derive(Debug, PartialEq)]
 pub enum Command {
@@ -6,24 +10,202 @@ pub enum Command {
         all: bool,
         user: Option<String>,
         sort_by: Option<String>,
+        pager: bool,
+        filter: Option<String>,
+        exclude: Option<String>,
+        all_threads: bool,
+        weights: Option<String>,
+        both: bool,
+        last_cpu: bool,
+        // Hide processes below this %CPU or resident memory (KB)
+        min_cpu: Option<f64>,
+        min_mem: Option<u64>,
+        // Table, or JSON (`--pretty` to indent it) / CSV for scripts
+        format: OutputFormat,
     },
     KillProcess {
         pid: u32,
         signal: Option<String>,
+        // Signal only this thread of the process instead of the whole process
+        tid: Option<u32>,
+        // Send SIGTERM, then SIGKILL if the process is still alive after this
+        // many seconds (`--escalate`, with `--grace` defaulting to 5)
+        escalate: Option<u64>,
+    },
+    // Signal every process owned by a user; only acts with `confirmed` (-y), and
+    // root or the current user additionally need `force`
+    KillUser {
+        user: String,
+        signal: Option<String>,
+        confirmed: bool,
+        force: bool,
+        // At most this many signals per second; unlimited when None
+        rate: Option<u32>,
+    },
+    // Signal processes by name; several matches need `all` to all be signalled
+    KillByName {
+        name: String,
+        signal: Option<String>,
+        all: bool,
+        // At most this many signals per second; unlimited when None
+        rate: Option<u32>,
+    },
+    // Signal a process and all of its descendants
+    KillTree {
+        pid: u32,
+        signal: Option<String>,
+        // At most this many signals per second; unlimited when None
+        rate: Option<u32>,
     },
     ProcessInfo {
         pid: u32,
         detailed: bool,
+        // Also print the chain of parent processes up to PID 1
+        ancestry: bool,
+        format: OutputFormat,
     },
     SystemStats {
         refresh_interval: Option<u64>,
+        history: Option<usize>,
+        no_bar: bool,
+        count: Option<u64>,
+        // Everything on one `key=value` line, for status bars
+        summary_only: bool,
     },
     SearchProcess {
         name: String,
         exact: bool,
+        count: bool,
+    },
+    ByUser {
+        count: bool,
+    },
+    ByName {
+        count: bool,
+    },
+    NiceDist,
+    AgeDist,
+    // Kernel memory (slab, stacks, page tables) from /proc/meminfo
+    KernelMem,
+    // PIDs in use against the kernel's pid_max
+    PidStat,
+    // Processes whose effective UID differs from their real UID
+    SetUid,
+    // The tool's own PID, resource usage and privileges
+    SelfInfo,
+    // Show, or with a mode set, how JSON/CSV records are flushed to stdout
+    Buffer {
+        mode: Option<Buffering>,
+    },
+    // Prometheus text format, printed or written to a textfile collector file
+    Metrics {
+        path: Option<String>,
     },
     Monitor { 
-        interval: u64 
+        interval: u64,
+        both: bool,
+        count: Option<u64>,
+        no_clear: bool,
+        sort_by: Option<String>,
+        // Timestamps in UTC instead of local time
+        utc: bool,
+        // Track PIDs from kernel process events instead of listing /proc every refresh
+        events: bool,
+        // After the first refresh, only show processes whose CPU, memory or state changed
+        only_changed: bool,
+        // (min, max) seconds between refreshes with --adaptive, which stretches
+        // the interval with global CPU usage instead of using `interval`
+        adaptive: Option<(u64, u64)>,
+        // Hide processes below this %CPU or resident memory (KB), like `ps`
+        min_cpu: Option<f64>,
+        min_mem: Option<u64>,
+        // Show only the first N processes, heaviest CPU first unless --sort says otherwise
+        top: Option<usize>,
+        // JSON and CSV print a record per refresh instead of redrawing the table
+        format: OutputFormat,
+    },
+    MemDetail {
+        pid: u32,
+    },
+    // TCP/UDP sockets of one process, or of every process we can inspect
+    Connections {
+        pid: Option<u32>,
+    },
+    // One-sentence summary of what a process is doing
+    Explain {
+        pid: u32,
+    },
+    // Kernel function the process is sleeping in
+    Wchan {
+        pid: u32,
+    },
+    // Change a process's scheduling priority, -20 (highest) to 19 (lowest)
+    Renice {
+        pid: u32,
+        nice: i32,
+    },
+    // A process against the other processes with the same name
+    Peers {
+        pid: u32,
+    },
+    // Processes nested under their parents, or only the subtree under `root`;
+    // `sort_by` (cpu|mem|io) orders the children of each process, heaviest
+    // first, instead of by PID
+    Tree {
+        root: Option<u32>,
+        sort_by: Option<String>,
+    },
+    ResetIo {
+        pid: u32,
+    },
+    Growth {
+        interval: u64,
+        top: usize,
+    },
+    Record {
+        pid: u32,
+        path: String,
+        interval: u64,
+        utc: bool,
+    },
+    // System-wide CPU, memory, swap and load appended to a CSV file
+    StatLog {
+        path: String,
+        interval: u64,
+        utc: bool,
+    },
+    // Sample a process's thread count to catch thread leaks
+    ThreadGrowth {
+        pid: u32,
+        interval: u64,
+    },
+    // One row per thread of a process: TID, name and state
+    Threads {
+        pid: u32,
+    },
+    // Follow one process's CPU, memory and I/O every `interval` seconds
+    Watch {
+        pid: u32,
+        interval: u64,
+    },
+    Churn {
+        interval: u64,
+        names: bool,
+        utc: bool,
+    },
+    CpuShare {
+        top: Option<usize>,
+    },
+    // Processes with memory in swap, most swapped first
+    Swapping {
+        top: Option<usize>,
+    },
+    WaitFor {
+        name: String,
+        timeout: Option<u64>,
+    },
+    Top1 {
+        resource: Resource,
     },
     Help,
     Exit,
@@ -33,11 +215,215 @@ pub enum Command {
 #[derive(Debug)]
 pub struct ParseResult {
     pub command: Command,
+    #[allow(dead_code)]
     pub raw_input: String,
 }
 
 pub struct CommandParser;
 
+// Value of a `--name VALUE` or `--name=VALUE` option, if present and valid
+fn option_value<T: FromStr>(args: &[&str], name: &str) -> Option<T> {
+    let mut value = None;
+    for (i, arg) in args.iter().enumerate() {
+        if let Some(v) = arg.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')) {
+            if let Ok(parsed) = v.parse() {
+                value = Some(parsed);
+            }
+        } else if *arg == name
+            && i + 1 < args.len()
+            && let Ok(parsed) = args[i + 1].parse()
+        {
+            // Handle case where the value is the next argument
+            value = Some(parsed);
+        }
+    }
+    value
+}
+
+#[derive(Debug, PartialEq)]
+struct Token {
+    text: String,
+    // Quoted tokens are taken literally, never as flags to expand
+    quoted: bool,
+}
+
+// Split input into words like a shell would: whitespace separates words, while
+// "double" and 'single' quotes keep spaces together. Inside double quotes and
+// outside quotes a backslash escapes the next character; single quotes are literal.
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    tokens.push(Token { text: std::mem::take(&mut current), quoted });
+                    in_word = false;
+                    quoted = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated ' quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => current.push(escaped),
+                            None => return Err("unterminated \" quote".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated \" quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        tokens.push(Token { text: current, quoted });
+    }
+    Ok(tokens)
+}
+
+// Split POSIX-style combined short flags so `-au root` reads as `-a -u root`.
+// Only unquoted all-letter clusters are expanded, leaving values like `-9`
+// alone; the command word itself is never touched. A letter that isn't one of
+// the command's short flags is an error rather than being silently dropped.
+fn expand_short_flags(tokens: Vec<Token>) -> Result<Vec<String>, String> {
+    let verb = tokens.first().map(|token| token.text.to_lowercase()).unwrap_or_default();
+    let known = command_flags(&verb);
+    let mut expanded = Vec::new();
+    for (i, token) in tokens.into_iter().enumerate() {
+        let cluster = token.text.strip_prefix('-').filter(|rest| {
+            i > 0
+                && !token.quoted
+                && rest.len() > 1
+                && rest.chars().all(|c| c.is_ascii_alphabetic())
+        });
+        let Some(flags) = cluster else {
+            expanded.push(token.text);
+            continue;
+        };
+        for flag in flags.chars().map(|flag| format!("-{}", flag)) {
+            if !known.contains(&flag.as_str()) {
+                return Err(format!("{}: unknown flag {} in {}", verb, flag, token.text));
+            }
+            expanded.push(flag);
+        }
+    }
+    Ok(expanded)
+}
+
+// Seconds `kill --escalate` waits after SIGTERM unless --grace is given
+const DEFAULT_GRACE: u64 = 5;
+
+// `monitor --adaptive` bounds unless --min-interval / --max-interval are given
+const DEFAULT_MIN_INTERVAL: u64 = 1;
+const DEFAULT_MAX_INTERVAL: u64 = 10;
+
+// `--count` asks for just the number of entries instead of the listing
+fn has_count_flag(args: &[&str]) -> bool {
+    args.contains(&"--count")
+}
+
+// `--format table|json|csv`, where `--pretty` indents the JSON for reading
+fn output_format(command: &str, args: &[&str]) -> Result<OutputFormat, String> {
+    let pretty = args.contains(&"--pretty");
+    let format = match option_value::<String>(args, "--format") {
+        None => OutputFormat::Table,
+        Some(name) => OutputFormat::parse(&name).ok_or_else(|| {
+            format!("{}: unknown format '{}' (expected table, json or csv)", command, name)
+        })?,
+    };
+    match format {
+        OutputFormat::Json { .. } => Ok(OutputFormat::Json { pretty }),
+        _ if pretty => Err(format!("{}: --pretty only applies to --format json", command)),
+        format => Ok(format),
+    }
+}
+
+// Every command verb the parser accepts, for tab completion and typo suggestions
+pub const COMMANDS: &[&str] = &[
+    "ps", "list", "kill", "killuser", "killall", "killtree", "info", "show", "stats", "status", "search",
+    "find", "byuser", "byname", "nicedist", "agedist", "pidstat", "setuid", "kmem", "self", "buffer",
+    "metrics", "mem", "explain", "wchan", "peers", "renice", "tree", "connections", "growth",
+    "record", "statlog", "threadgrowth", "threads", "watch", "cpushare", "swapping", "waitfor", "top1", "churn",
+    "resetio", "monitor", "help", "exit", "quit",
+];
+
+// Flags a command understands, offered once its verb has been typed in full
+pub fn command_flags(command: &str) -> &'static [&'static str] {
+    match command {
+        "ps" | "list" => &[
+            "-a", "--all", "-u", "--user", "-s", "--sort", "--filter", "--exclude", "--pager",
+            "--all-threads", "--weights", "--both", "--last-cpu", "--min-cpu", "--min-mem",
+            "--format", "--pretty",
+        ],
+        "kill" => &["--tid", "--escalate", "--grace"],
+        "killuser" => &["-y", "--yes", "--force", "--rate"],
+        "killall" => &["--all", "--rate"],
+        "killtree" => &["--rate"],
+        "info" | "show" => &["-d", "--detailed", "--ancestry", "--format", "--pretty"],
+        "stats" | "status" => &["--refresh", "--history", "--no-bar", "--count", "--summary-only"],
+        "search" | "find" => &["-e", "--exact", "-c", "--count"],
+        "byuser" | "byname" => &["--count"],
+        "tree" => &["-s", "--sort"],
+        "growth" | "cpushare" | "swapping" => &["--top"],
+        "record" | "statlog" => &["--utc"],
+        "churn" => &["--names", "--utc"],
+        "waitfor" => &["--timeout"],
+        "monitor" => &[
+            "-b", "--both", "--count", "--no-clear", "-s", "--sort", "--utc", "--events",
+            "--only-changed", "--adaptive", "--min-interval", "--max-interval", "--min-cpu",
+            "--min-mem", "--top", "--format", "--pretty",
+        ],
+        _ => &[],
+    }
+}
+
+// Typos further than this from every command get no suggestion
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+// Number of single-character insertions, deletions or substitutions turning a into b
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 impl CommandParser {
     pub fn new() -> Self {
         CommandParser
@@ -52,29 +438,137 @@ impl CommandParser {
             };
         }
 
-        let parts: Vec<&str> = input.split_whitespace().collect();
+        let tokens = match tokenize(input).and_then(expand_short_flags) {
+            Ok(tokens) => tokens,
+            Err(message) => {
+                return ParseResult {
+                    command: Command::Unknown(message),
+                    raw_input: input.to_string(),
+                }
+            }
+        };
+        let parts: Vec<&str> = tokens.iter().map(String::as_str).collect();
         let command = parts[0].to_lowercase();
 
         match command.as_str() {
             "ps" | "list" => self.parse_list_command(&parts[1..]),
             "kill" => self.parse_kill_command(&parts[1..]),
+            "killuser" => self.parse_killuser_command(&parts[1..]),
+            "killall" => self.parse_killall_command(&parts[1..]),
+            "killtree" => self.parse_killtree_command(&parts[1..]),
             "info" | "show" => self.parse_info_command(&parts[1..]),
             "stats" | "status" => self.parse_stats_command(&parts[1..]),
             "search" | "find" => self.parse_search_command(&parts[1..]),
-            "monitor" => {
-                // Use parts after command word as args:
-                let args = &parts[1..];
-                // Now you can use args here safely
-                let interval = if !args.is_empty() {
-                    args[0].parse::<u64>().unwrap_or(2)
-                } else {
-                    2
-                };
-                return ParseResult {
-                    command: Command::Monitor { interval },
+            "byuser" => ParseResult {
+                command: Command::ByUser { count: has_count_flag(&parts[1..]) },
+                raw_input: input.to_string(),
+            },
+            "byname" => ParseResult {
+                command: Command::ByName { count: has_count_flag(&parts[1..]) },
+                raw_input: input.to_string(),
+            },
+            "nicedist" => ParseResult {
+                command: Command::NiceDist,
+                raw_input: input.to_string(),
+            },
+            "agedist" => ParseResult {
+                command: Command::AgeDist,
+                raw_input: input.to_string(),
+            },
+            "pidstat" => ParseResult {
+                command: Command::PidStat,
+                raw_input: input.to_string(),
+            },
+            "setuid" => ParseResult {
+                command: Command::SetUid,
+                raw_input: input.to_string(),
+            },
+            "kmem" => ParseResult {
+                command: Command::KernelMem,
+                raw_input: input.to_string(),
+            },
+            "self" => ParseResult {
+                command: Command::SelfInfo,
+                raw_input: input.to_string(),
+            },
+            "buffer" => match parts.get(1).map(|mode| Buffering::parse(mode)) {
+                None => ParseResult {
+                    command: Command::Buffer { mode: None },
+                    raw_input: input.to_string(),
+                },
+                Some(Some(mode)) => ParseResult {
+                    command: Command::Buffer { mode: Some(mode) },
+                    raw_input: input.to_string(),
+                },
+                Some(None) => self.unknown("buffer: expected line or block".to_string(), &parts[1..]),
+            },
+            "metrics" => ParseResult {
+                command: Command::Metrics { path: parts.get(1).map(|path| path.to_string()) },
+                raw_input: input.to_string(),
+            },
+            "mem" => self.parse_mem_command(&parts[1..]),
+            "explain" => self.parse_explain_command(&parts[1..]),
+            "wchan" => self.parse_wchan_command(&parts[1..]),
+            "peers" => self.parse_peers_command(&parts[1..]),
+            "renice" => self.parse_renice_command(&parts[1..]),
+            "tree" => self.parse_tree_command(&parts[1..]),
+            "connections" => match parts.get(1).map(|pid| pid.parse::<u32>()) {
+                None => ParseResult {
+                    command: Command::Connections { pid: None },
+                    raw_input: input.to_string(),
+                },
+                Some(Ok(pid)) => ParseResult {
+                    command: Command::Connections { pid: Some(pid) },
+                    raw_input: input.to_string(),
+                },
+                Some(Err(_)) => self.unknown(
+                    format!("connections: invalid PID '{}'", parts[1]),
+                    &parts[1..],
+                ),
+            },
+            "growth" => self.parse_growth_command(&parts[1..]),
+            "record" => self.parse_record_command(&parts[1..]),
+            "statlog" => self.parse_statlog_command(&parts[1..]),
+            "threadgrowth" => self.parse_threadgrowth_command(&parts[1..]),
+            "watch" => self.parse_watch_command(&parts[1..]),
+            "threads" => match self.parse_pid_arg("threads", &parts[1..]) {
+                Ok(pid) => ParseResult {
+                    command: Command::Threads { pid },
+                    raw_input: input.to_string(),
+                },
+                Err(message) => self.unknown(message, &parts[1..]),
+            },
+            "cpushare" => ParseResult {
+                command: Command::CpuShare { top: option_value(&parts[1..], "--top") },
+                raw_input: input.to_string(),
+            },
+            "swapping" => ParseResult {
+                command: Command::Swapping { top: option_value(&parts[1..], "--top") },
+                raw_input: input.to_string(),
+            },
+            "waitfor" => self.parse_waitfor_command(&parts[1..]),
+            "top1" => match parts.get(1).and_then(|name| Resource::parse(name)) {
+                Some(resource) => ParseResult {
+                    command: Command::Top1 { resource },
                     raw_input: input.to_string(),
-                };
+                },
+                None => self.unknown("top1: expected cpu, mem or io".to_string(), &parts[1..]),
             },
+            "churn" => {
+                let args = &parts[1..];
+                let interval = args
+                    .first()
+                    .and_then(|arg| arg.parse::<u64>().ok())
+                    .unwrap_or(1);
+                let names = args.contains(&"--names");
+                let utc = args.contains(&"--utc");
+                ParseResult {
+                    command: Command::Churn { interval, names, utc },
+                    raw_input: input.to_string(),
+                }
+            }
+            "resetio" => self.parse_resetio_command(&parts[1..]),
+            "monitor" => self.parse_monitor_command(&parts[1..]),
             "help" => ParseResult {
                 command: Command::Help,
                 raw_input: input.to_string(),
@@ -90,26 +584,97 @@ impl CommandParser {
         }
     }
 
+    fn parse_monitor_command(&self, args: &[&str]) -> ParseResult {
+        // The refresh interval is the first argument when it's a number
+        let interval = args
+            .first()
+            .and_then(|arg| arg.parse::<u64>().ok())
+            .unwrap_or(2);
+        let both = args.contains(&"--both") || args.contains(&"-b");
+        let count = option_value(args, "--count");
+        let no_clear = args.contains(&"--no-clear");
+        let sort_by = option_value(args, "--sort").or_else(|| option_value(args, "-s"));
+        let utc = args.contains(&"--utc");
+        let events = args.contains(&"--events");
+        let only_changed = args.contains(&"--only-changed");
+        let adaptive = args.contains(&"--adaptive").then(|| {
+            let min = option_value(args, "--min-interval").unwrap_or(DEFAULT_MIN_INTERVAL);
+            let max = option_value(args, "--max-interval").unwrap_or(DEFAULT_MAX_INTERVAL);
+            (min.max(1), max.max(min.max(1)))
+        });
+        let min_cpu = option_value(args, "--min-cpu");
+        let min_mem = option_value(args, "--min-mem");
+        let top = option_value(args, "--top");
+        let format = match output_format("monitor", args) {
+            Ok(format) => format,
+            Err(message) => return self.unknown(message, args),
+        };
+
+        ParseResult {
+            command: Command::Monitor {
+                interval,
+                both,
+                count,
+                no_clear,
+                sort_by,
+                utc,
+                events,
+                only_changed,
+                adaptive,
+                min_cpu,
+                min_mem,
+                top,
+                format,
+            },
+            raw_input: args.join(" "),
+        }
+    }
+
     fn parse_list_command(&self, args: &[&str]) -> ParseResult {
         let mut all = false;
         let mut user = None;
         let mut sort_by = None;
+        let mut pager = false;
+        let mut filter = None;
+        let mut exclude = None;
+        let mut all_threads = false;
+        let mut weights = None;
+        let mut both = false;
+        let mut last_cpu = false;
+        let min_cpu = option_value(args, "--min-cpu");
+        let min_mem = option_value(args, "--min-mem");
+        let format = match output_format("ps", args) {
+            Ok(format) => format,
+            Err(message) => return self.unknown(message, args),
+        };
 
         let mut i = 0;
         while i < args.len() {
             match args[i] {
                 "-a" | "--all" => all = true,
-                "-u" | "--user" => {
-                    if i + 1 < args.len() {
-                        user = Some(args[i + 1].to_string());
-                        i += 1;
-                    }
+                "-u" | "--user" if i + 1 < args.len() => {
+                    user = Some(args[i + 1].to_string());
+                    i += 1;
                 }
-                "-s" | "--sort" => {
-                    if i + 1 < args.len() {
-                        sort_by = Some(args[i + 1].to_string());
-                        i += 1;
-                    }
+                "-s" | "--sort" if i + 1 < args.len() => {
+                    sort_by = Some(args[i + 1].to_string());
+                    i += 1;
+                }
+                "--pager" => pager = true,
+                "--all-threads" => all_threads = true,
+                "--both" => both = true,
+                "--last-cpu" => last_cpu = true,
+                "--filter" if i + 1 < args.len() => {
+                    filter = Some(args[i + 1].to_string());
+                    i += 1;
+                }
+                "--exclude" if i + 1 < args.len() => {
+                    exclude = Some(args[i + 1].to_string());
+                    i += 1;
+                }
+                "--weights" if i + 1 < args.len() => {
+                    weights = Some(args[i + 1].to_string());
+                    i += 1;
                 }
                 _ => {}
             }
@@ -117,37 +682,171 @@ impl CommandParser {
         }
 
         ParseResult {
-            command: Command::ListProcesses { all, user, sort_by },
+            command: Command::ListProcesses {
+                all,
+                user,
+                sort_by,
+                pager,
+                filter,
+                exclude,
+                all_threads,
+                weights,
+                both,
+                last_cpu,
+                min_cpu,
+                min_mem,
+                format,
+            },
             raw_input: args.join(" "),
         }
     }
 
     fn parse_kill_command(&self, args: &[&str]) -> ParseResult {
-        if args.is_empty() {
-            return ParseResult {
-                command: Command::Unknown("kill: missing PID".to_string()),
-                raw_input: args.join(" "),
-            };
+        let mut pid = None;
+        let mut signal = None;
+        let mut tid = None;
+        let mut escalate = false;
+        let mut grace = DEFAULT_GRACE;
+        let mut i = 0;
+        while i < args.len() {
+            match args[i] {
+                "--tid" => {
+                    let value = args.get(i + 1).copied().unwrap_or("");
+                    match value.parse() {
+                        Ok(value) => tid = Some(value),
+                        Err(_) => {
+                            return self.unknown(format!("kill: invalid TID '{}'", value), args)
+                        }
+                    }
+                    i += 1;
+                }
+                "--escalate" => escalate = true,
+                "--grace" => {
+                    let value = args.get(i + 1).copied().unwrap_or("");
+                    match value.parse() {
+                        Ok(value) => grace = value,
+                        Err(_) => {
+                            return self.unknown(format!("kill: invalid grace period '{}'", value), args)
+                        }
+                    }
+                    i += 1;
+                }
+                // The PID comes first, so `kill --escalate PID` works as well as `kill PID --escalate`
+                arg if pid.is_none() => match arg.parse() {
+                    Ok(value) => pid = Some(value),
+                    Err(_) => return self.unknown(format!("kill: invalid PID '{}'", arg), args),
+                },
+                arg => signal = Some(arg.to_string()),
+            }
+            i += 1;
         }
 
-        let pid = match args[0].parse() {
+        let Some(pid) = pid else {
+            return self.unknown("kill: missing PID".to_string(), args);
+        };
+        if escalate && (tid.is_some() || signal.is_some()) {
+            return self.unknown(
+                "kill: --escalate always sends SIGTERM then SIGKILL to the whole process".to_string(),
+                args,
+            );
+        }
+
+        ParseResult {
+            command: Command::KillProcess { pid, signal, tid, escalate: escalate.then_some(grace) },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_killuser_command(&self, args: &[&str]) -> ParseResult {
+        // Skip flags, and the value that follows --rate
+        let mut positional = args
+            .iter()
+            .enumerate()
+            .filter(|&(i, arg)| !arg.starts_with('-') && (i == 0 || args[i - 1] != "--rate"))
+            .map(|(_, arg)| arg);
+        let Some(user) = positional.next() else {
+            return self.unknown("killuser: missing user".to_string(), args);
+        };
+        let signal = positional.next().map(|signal| signal.to_string());
+
+        ParseResult {
+            command: Command::KillUser {
+                user: user.to_string(),
+                signal,
+                confirmed: args.contains(&"-y") || args.contains(&"--yes"),
+                force: args.contains(&"--force"),
+                rate: option_value(args, "--rate").filter(|&rate| rate > 0),
+            },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_killall_command(&self, args: &[&str]) -> ParseResult {
+        // Skip flags, and the value that follows --rate
+        let mut positional = args
+            .iter()
+            .enumerate()
+            .filter(|&(i, arg)| !arg.starts_with('-') && (i == 0 || args[i - 1] != "--rate"))
+            .map(|(_, arg)| arg);
+        let Some(name) = positional.next() else {
+            return self.unknown("killall: missing process name".to_string(), args);
+        };
+        let signal = positional.next().map(|signal| signal.to_string());
+
+        ParseResult {
+            command: Command::KillByName {
+                name: name.to_string(),
+                signal,
+                all: args.contains(&"--all"),
+                rate: option_value(args, "--rate").filter(|&rate| rate > 0),
+            },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_killtree_command(&self, args: &[&str]) -> ParseResult {
+        // Skip flags, and the value that follows --rate
+        let positional: Vec<&str> = args
+            .iter()
+            .enumerate()
+            .filter(|&(i, arg)| !arg.starts_with('-') && (i == 0 || args[i - 1] != "--rate"))
+            .map(|(_, arg)| *arg)
+            .collect();
+        let pid = match self.parse_pid_arg("killtree", &positional) {
             Ok(pid) => pid,
-            Err(_) => {
-                return ParseResult {
-                    command: Command::Unknown(format!("kill: invalid PID '{}'", args[0])),
-                    raw_input: args.join(" "),
-                }
-            }
+            Err(message) => return self.unknown(message, args),
         };
 
-        let signal = if args.len() > 1 {
-            Some(args[1].to_string())
-        } else {
-            None
+        ParseResult {
+            command: Command::KillTree {
+                pid,
+                signal: positional.get(1).map(|signal| signal.to_string()),
+                rate: option_value(args, "--rate").filter(|&rate| rate > 0),
+            },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_tree_command(&self, args: &[&str]) -> ParseResult {
+        // Skip flags, and the value that follows --sort/-s
+        let positional = args
+            .iter()
+            .enumerate()
+            .find(|&(i, arg)| !arg.starts_with('-') && (i == 0 || !matches!(args[i - 1], "--sort" | "-s")))
+            .map(|(_, arg)| arg);
+        let root = match positional {
+            None => None,
+            Some(pid) => match pid.parse() {
+                Ok(pid) => Some(pid),
+                Err(_) => return self.unknown(format!("tree: invalid PID '{}'", pid), args),
+            },
         };
 
         ParseResult {
-            command: Command::KillProcess { pid, signal },
+            command: Command::Tree {
+                root,
+                sort_by: option_value(args, "--sort").or_else(|| option_value(args, "-s")),
+            },
             raw_input: args.join(" "),
         }
     }
@@ -171,90 +870,1079 @@ impl CommandParser {
         };
 
         let detailed = args.iter().any(|&arg| arg == "-d" || arg == "--detailed");
+        let ancestry = args.contains(&"--ancestry");
+        let format = match output_format("info", args) {
+            Ok(format) => format,
+            Err(message) => return self.unknown(message, args),
+        };
 
         ParseResult {
-            command: Command::ProcessInfo { pid, detailed },
+            command: Command::ProcessInfo { pid, detailed, ancestry, format },
             raw_input: args.join(" "),
         }
     }
 
-    fn parse_stats_command(&self, args: &[&str]) -> ParseResult {
-        let mut refresh_interval = None;
-
-        for (i, arg) in args.iter().enumerate() {
-            if let Some(num) = arg.strip_prefix("--refresh=") {
-                if let Ok(interval) = num.parse() {
-                    refresh_interval = Some(interval);
-                }
-            } else if *arg == "--refresh" {
-                // Handle case where refresh interval is next argument
-                if i + 1 < args.len() {
-                    if let Ok(interval) = args[i + 1].parse::<u64>() {
-                        refresh_interval = Some(interval);
-                    }
-                }
-            }
+    // Parse the leading PID argument shared by the per-process commands
+    fn parse_pid_arg(&self, name: &str, args: &[&str]) -> Result<u32, String> {
+        match args.first() {
+            None => Err(format!("{}: missing PID", name)),
+            Some(arg) => arg
+                .parse()
+                .map_err(|_| format!("{}: invalid PID '{}'", name, arg)),
         }
+    }
+
+    // The command the first word of `input` was probably meant to be, if it isn't
+    // one already and is within a couple of typos of one
+    pub fn suggest(&self, input: &str) -> Option<&'static str> {
+        let word = input.split_whitespace().next()?.to_lowercase();
+        let (distance, command) = COMMANDS
+            .iter()
+            .map(|command| (edit_distance(&word, command), *command))
+            .min_by_key(|(distance, _)| *distance)?;
+        (1..=MAX_SUGGESTION_DISTANCE).contains(&distance).then_some(command)
+    }
 
+    fn unknown(&self, message: String, args: &[&str]) -> ParseResult {
         ParseResult {
-            command: Command::SystemStats { refresh_interval },
+            command: Command::Unknown(message),
             raw_input: args.join(" "),
         }
     }
 
-    fn parse_search_command(&self, args: &[&str]) -> ParseResult {
-        if args.is_empty() {
-            return ParseResult {
-                command: Command::Unknown("search: missing process name".to_string()),
-                raw_input: args.join(" "),
-            };
-        }
-
-        let name = args[0].to_string();
-        let exact = args.iter().any(|&arg| arg == "-e" || arg == "--exact");
+    fn parse_mem_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("mem", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
 
         ParseResult {
-            command: Command::SearchProcess { name, exact },
+            command: Command::MemDetail { pid },
             raw_input: args.join(" "),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn parse_explain_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("explain", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
 
-    #[test]
-    fn test_parse_list_command() {
-        let parser = CommandParser::new();
-        let result = parser.parse("ps -a -u root --sort cpu");
-        
-        if let Command::ListProcesses { all, user, sort_by } = result.command {
-            assert!(all);
-            assert_eq!(user, Some("root".to_string()));
-            assert_eq!(sort_by, Some("cpu".to_string()));
-        } else {
-            panic!("Expected ListProcesses command");
+        ParseResult {
+            command: Command::Explain { pid },
+            raw_input: args.join(" "),
         }
     }
 
-    #[test]
-    fn test_parse_kill_command() {
-        let parser = CommandParser::new();
-        let result = parser.parse("kill 1234 SIGTERM");
-        
-        if let Command::KillProcess { pid, signal } = result.command {
-            assert_eq!(pid, 1234);
-            assert_eq!(signal, Some("SIGTERM".to_string()));
-        } else {
-            panic!("Expected KillProcess command");
+    fn parse_wchan_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("wchan", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
+
+        ParseResult {
+            command: Command::Wchan { pid },
+            raw_input: args.join(" "),
         }
     }
 
-    #[test]
-    fn test_parse_help() {
+    fn parse_peers_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("peers", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
+
+        ParseResult {
+            command: Command::Peers { pid },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_renice_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("renice", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
+        let nice = match args.get(1).map(|nice| nice.parse::<i32>()) {
+            None => return self.unknown("renice: missing nice value".to_string(), args),
+            Some(Ok(nice)) if (-20..=19).contains(&nice) => nice,
+            Some(_) => {
+                return self.unknown(
+                    format!("renice: invalid nice value '{}' (must be between -20 and 19)", args[1]),
+                    args,
+                )
+            }
+        };
+
+        ParseResult {
+            command: Command::Renice { pid, nice },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_resetio_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("resetio", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
+
+        ParseResult {
+            command: Command::ResetIo { pid },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_growth_command(&self, args: &[&str]) -> ParseResult {
+        // The interval is positional; anything else is a flag or its value
+        let interval = args
+            .first()
+            .and_then(|arg| arg.parse::<u64>().ok())
+            .unwrap_or(5);
+        let top = option_value(args, "--top").unwrap_or(10);
+
+        ParseResult {
+            command: Command::Growth { interval, top },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_record_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("record", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
+
+        let path = match args.get(1) {
+            Some(path) => path.to_string(),
+            None => {
+                return ParseResult {
+                    command: Command::Unknown("record: missing output file".to_string()),
+                    raw_input: args.join(" "),
+                }
+            }
+        };
+
+        let interval = args
+            .get(2)
+            .and_then(|arg| arg.parse::<u64>().ok())
+            .unwrap_or(1);
+        let utc = args.contains(&"--utc");
+
+        ParseResult {
+            command: Command::Record { pid, path, interval, utc },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_statlog_command(&self, args: &[&str]) -> ParseResult {
+        let Some(path) = args.first().filter(|arg| !arg.starts_with('-')) else {
+            return self.unknown("statlog: missing output file".to_string(), args);
+        };
+        let interval = args
+            .get(1)
+            .and_then(|arg| arg.parse::<u64>().ok())
+            .unwrap_or(1);
+
+        ParseResult {
+            command: Command::StatLog { path: path.to_string(), interval, utc: args.contains(&"--utc") },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_threadgrowth_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("threadgrowth", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
+        let interval = args
+            .get(1)
+            .and_then(|arg| arg.parse::<u64>().ok())
+            .unwrap_or(1);
+
+        ParseResult {
+            command: Command::ThreadGrowth { pid, interval },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_watch_command(&self, args: &[&str]) -> ParseResult {
+        let pid = match self.parse_pid_arg("watch", args) {
+            Ok(pid) => pid,
+            Err(message) => return self.unknown(message, args),
+        };
+        let interval = args
+            .get(1)
+            .and_then(|arg| arg.parse::<u64>().ok())
+            .filter(|&seconds| seconds > 0)
+            .unwrap_or(1);
+
+        ParseResult {
+            command: Command::Watch { pid, interval },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_waitfor_command(&self, args: &[&str]) -> ParseResult {
+        let name = match args.first() {
+            Some(name) if !name.starts_with("--") => name.to_string(),
+            _ => return self.unknown("waitfor: missing process name".to_string(), args),
+        };
+        let timeout = option_value(args, "--timeout");
+
+        ParseResult {
+            command: Command::WaitFor { name, timeout },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_stats_command(&self, args: &[&str]) -> ParseResult {
+        let refresh_interval = option_value(args, "--refresh");
+        let history = option_value(args, "--history");
+        let no_bar = args.contains(&"--no-bar");
+        let count = option_value(args, "--count");
+        let summary_only = args.contains(&"--summary-only");
+
+        ParseResult {
+            command: Command::SystemStats { refresh_interval, history, no_bar, count, summary_only },
+            raw_input: args.join(" "),
+        }
+    }
+
+    fn parse_search_command(&self, args: &[&str]) -> ParseResult {
+        // The flags may come before the name
+        let Some(name) = args.iter().find(|arg| !arg.starts_with('-')) else {
+            return ParseResult {
+                command: Command::Unknown("search: missing process name".to_string()),
+                raw_input: args.join(" "),
+            };
+        };
+        let name = name.to_string();
+        let exact = args.iter().any(|&arg| arg == "-e" || arg == "--exact");
+        let count = has_count_flag(args) || args.contains(&"-c");
+
+        ParseResult {
+            command: Command::SearchProcess { name, exact, count },
+            raw_input: args.join(" "),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list_command() {
+        let parser = CommandParser::new();
+        let result = parser.parse("ps -a -u root --sort cpu");
+        
+        if let Command::ListProcesses { all, user, sort_by, pager, .. } = result.command {
+            assert!(all);
+            assert_eq!(user, Some("root".to_string()));
+            assert_eq!(sort_by, Some("cpu".to_string()));
+            assert!(!pager);
+        } else {
+            panic!("Expected ListProcesses command");
+        }
+    }
+
+    #[test]
+    fn test_parse_kill_command() {
+        let parser = CommandParser::new();
+        let result = parser.parse("kill 1234 SIGTERM");
+        
+        if let Command::KillProcess { pid, signal, tid, escalate } = result.command {
+            assert_eq!(pid, 1234);
+            assert_eq!(signal, Some("SIGTERM".to_string()));
+            assert_eq!(tid, None);
+            assert_eq!(escalate, None);
+        } else {
+            panic!("Expected KillProcess command");
+        }
+    }
+
+    #[test]
+    fn test_parse_killall_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("killall nginx").command,
+            Command::KillByName { name: "nginx".to_string(), signal: None, all: false, rate: None }
+        );
+        assert_eq!(
+            parser.parse("killall --rate 5 nginx HUP --all").command,
+            Command::KillByName {
+                name: "nginx".to_string(),
+                signal: Some("HUP".to_string()),
+                all: true,
+                rate: Some(5),
+            }
+        );
+        assert!(matches!(parser.parse("killall").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_killtree_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("killtree 42").command,
+            Command::KillTree { pid: 42, signal: None, rate: None }
+        );
+        assert_eq!(
+            parser.parse("killtree --rate 10 42 KILL").command,
+            Command::KillTree { pid: 42, signal: Some("KILL".to_string()), rate: Some(10) }
+        );
+        assert!(matches!(parser.parse("killtree").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("killtree abc").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_killuser_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("killuser bob").command,
+            Command::KillUser {
+                user: "bob".to_string(),
+                signal: None,
+                confirmed: false,
+                force: false,
+                rate: None,
+            }
+        );
+        assert_eq!(
+            parser.parse("killuser -y bob SIGKILL --force").command,
+            Command::KillUser {
+                user: "bob".to_string(),
+                signal: Some("SIGKILL".to_string()),
+                confirmed: true,
+                force: true,
+                rate: None,
+            }
+        );
+        // The rate's value isn't mistaken for the user or signal
+        assert_eq!(
+            parser.parse("killuser --rate 20 bob -y").command,
+            Command::KillUser {
+                user: "bob".to_string(),
+                signal: None,
+                confirmed: true,
+                force: false,
+                rate: Some(20),
+            }
+        );
+        assert!(matches!(
+            parser.parse("killuser bob --rate 0").command,
+            Command::KillUser { rate: None, .. }
+        ));
+        assert!(matches!(parser.parse("killuser -y").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_killuser_signal_forms() {
+        use crate::signals::parse_signal;
+        use nix::sys::signal::Signal;
+
+        // The signal reaches the handler as typed and is resolved there, so
+        // numbers and bare names work and a typo is refused rather than sent
+        let signal = |line: &str| match CommandParser::new().parse(line).command {
+            Command::KillUser { signal, .. } => signal.as_deref().and_then(parse_signal),
+            other => panic!("Expected KillUser command, got {:?}", other),
+        };
+        assert_eq!(signal("killuser -y bob 9"), Some(Signal::SIGKILL));
+        assert_eq!(signal("killuser -y bob hup"), Some(Signal::SIGHUP));
+        assert_eq!(signal("killuser -y bob SIGUSR1"), Some(Signal::SIGUSR1));
+        assert_eq!(signal("killuser -y bob SIGKIL"), None);
+    }
+
+    #[test]
+    fn test_parse_kill_thread() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("kill 1234 --tid 1240 SIGKILL").command,
+            Command::KillProcess { pid: 1234, signal: Some("SIGKILL".to_string()), tid: Some(1240), escalate: None }
+        );
+        assert_eq!(
+            parser.parse("kill 1234 --tid 1240").command,
+            Command::KillProcess { pid: 1234, signal: None, tid: Some(1240), escalate: None }
+        );
+        assert!(matches!(parser.parse("kill 1234 --tid").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("kill 1234 --tid abc").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_kill_tid_signal_forms() {
+        use crate::signals::parse_signal;
+        use nix::sys::signal::Signal;
+
+        // --tid goes through the same signal lookup as a whole-process kill
+        let signal = |line: &str| match CommandParser::new().parse(line).command {
+            Command::KillProcess { signal, tid: Some(1240), .. } => signal.as_deref().and_then(parse_signal),
+            other => panic!("Expected KillProcess command with a TID, got {:?}", other),
+        };
+        assert_eq!(signal("kill 1234 --tid 1240 10"), Some(Signal::SIGUSR1));
+        assert_eq!(signal("kill 1234 --tid 1240 stop"), Some(Signal::SIGSTOP));
+        assert_eq!(signal("kill 1234 --tid 1240 SIGCONT"), Some(Signal::SIGCONT));
+        assert_eq!(signal("kill 1234 --tid 1240 BOGUS"), None);
+    }
+
+    #[test]
+    fn test_parse_kill_escalate() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("kill --escalate 1234").command,
+            Command::KillProcess { pid: 1234, signal: None, tid: None, escalate: Some(5) }
+        );
+        assert_eq!(
+            parser.parse("kill 1234 --escalate --grace 10").command,
+            Command::KillProcess { pid: 1234, signal: None, tid: None, escalate: Some(10) }
+        );
+        assert!(matches!(parser.parse("kill --escalate").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("kill 1234 --escalate --grace x").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("kill 1234 --escalate SIGKILL").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("kill 1234 --escalate --tid 1240").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_resetio_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("resetio 42").command, Command::ResetIo { pid: 42 });
+        assert!(matches!(parser.parse("resetio").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("resetio abc").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_mem_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("mem 42").command, Command::MemDetail { pid: 42 });
+        assert!(matches!(parser.parse("mem").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("mem abc").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("mem -1").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_explain_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("explain 123").command, Command::Explain { pid: 123 });
+        assert!(matches!(parser.parse("explain").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_wchan_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("wchan 123").command, Command::Wchan { pid: 123 });
+        assert!(matches!(parser.parse("wchan abc").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_peers_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("peers 812").command, Command::Peers { pid: 812 });
+        assert!(matches!(parser.parse("peers").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_renice_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("renice 812 10").command, Command::Renice { pid: 812, nice: 10 });
+        assert_eq!(parser.parse("renice 812 -20").command, Command::Renice { pid: 812, nice: -20 });
+        assert!(matches!(parser.parse("renice 812 20").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("renice 812 -21").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("renice 812 high").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("renice 812").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("renice").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_tree_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("tree").command, Command::Tree { root: None, sort_by: None });
+        assert_eq!(
+            parser.parse("tree --sort cpu").command,
+            Command::Tree { root: None, sort_by: Some("cpu".to_string()) }
+        );
+        assert_eq!(
+            parser.parse("tree -s mem").command,
+            Command::Tree { root: None, sort_by: Some("mem".to_string()) }
+        );
+        assert_eq!(parser.parse("tree 812").command, Command::Tree { root: Some(812), sort_by: None });
+        assert_eq!(
+            parser.parse("tree --sort cpu 812").command,
+            Command::Tree { root: Some(812), sort_by: Some("cpu".to_string()) }
+        );
+        assert!(matches!(parser.parse("tree sshd").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_connections_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("connections").command, Command::Connections { pid: None });
+        assert_eq!(
+            parser.parse("connections 42").command,
+            Command::Connections { pid: Some(42) }
+        );
+        assert!(matches!(parser.parse("connections abc").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_count_flag() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("search nginx --count").command,
+            Command::SearchProcess { name: "nginx".to_string(), exact: false, count: true }
+        );
+        assert_eq!(
+            parser.parse("search --count -e nginx").command,
+            Command::SearchProcess { name: "nginx".to_string(), exact: true, count: true }
+        );
+        assert!(matches!(parser.parse("search --exact").command, Command::Unknown(_)));
+        assert_eq!(parser.parse("byuser --count").command, Command::ByUser { count: true });
+        assert_eq!(parser.parse("byname").command, Command::ByName { count: false });
+        assert_eq!(parser.parse("byname --count").command, Command::ByName { count: true });
+    }
+
+    #[test]
+    fn test_parse_distribution_commands() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("nicedist").command, Command::NiceDist);
+        assert_eq!(parser.parse("agedist").command, Command::AgeDist);
+    }
+
+    #[test]
+    fn test_parse_self_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("self").command, Command::SelfInfo);
+    }
+
+    #[test]
+    fn test_parse_kmem_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("kmem").command, Command::KernelMem);
+    }
+
+    #[test]
+    fn test_parse_pidstat_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("pidstat").command, Command::PidStat);
+    }
+
+    #[test]
+    fn test_parse_setuid_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("setuid").command, Command::SetUid);
+        assert_eq!(parser.parse("SETUID").command, Command::SetUid);
+    }
+
+    #[test]
+    fn test_parse_metrics_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("metrics").command, Command::Metrics { path: None });
+        assert_eq!(
+            parser.parse("metrics /tmp/lpm.prom").command,
+            Command::Metrics { path: Some("/tmp/lpm.prom".to_string()) }
+        );
+    }
+
+    #[test]
+    fn test_parse_stats_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("stats --refresh 2 --history=10").command,
+            Command::SystemStats {
+                refresh_interval: Some(2),
+                history: Some(10),
+                no_bar: false,
+                count: None,
+                summary_only: false,
+            }
+        );
+        assert_eq!(
+            parser.parse("stats --refresh=5 --no-bar").command,
+            Command::SystemStats {
+                refresh_interval: Some(5),
+                history: None,
+                no_bar: true,
+                count: None,
+                summary_only: false,
+            }
+        );
+        assert!(matches!(
+            parser.parse("stats --summary-only --refresh 1").command,
+            Command::SystemStats { refresh_interval: Some(1), summary_only: true, .. }
+        ));
+        assert!(matches!(
+            parser.parse("stats --refresh 1 --count 3").command,
+            Command::SystemStats { count: Some(3), .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_growth_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("growth 30 --top 5").command,
+            Command::Growth { interval: 30, top: 5 }
+        );
+        assert_eq!(parser.parse("growth").command, Command::Growth { interval: 5, top: 10 });
+        assert_eq!(
+            parser.parse("growth --top 3").command,
+            Command::Growth { interval: 5, top: 3 }
+        );
+    }
+
+    #[test]
+    fn test_parse_record_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("record 42 out.csv 5").command,
+            Command::Record { pid: 42, path: "out.csv".to_string(), interval: 5, utc: false }
+        );
+        assert_eq!(
+            parser.parse("record 42 out.csv").command,
+            Command::Record { pid: 42, path: "out.csv".to_string(), interval: 1, utc: false }
+        );
+        assert_eq!(
+            parser.parse("record 42 out.csv 5 --utc").command,
+            Command::Record { pid: 42, path: "out.csv".to_string(), interval: 5, utc: true }
+        );
+        assert!(matches!(parser.parse("record 42").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_statlog_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("statlog stats.csv").command,
+            Command::StatLog { path: "stats.csv".to_string(), interval: 1, utc: false }
+        );
+        assert_eq!(
+            parser.parse("statlog stats.csv 10 --utc").command,
+            Command::StatLog { path: "stats.csv".to_string(), interval: 10, utc: true }
+        );
+        assert!(matches!(parser.parse("statlog").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_threadgrowth_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("threadgrowth 42").command,
+            Command::ThreadGrowth { pid: 42, interval: 1 }
+        );
+        assert_eq!(
+            parser.parse("threadgrowth 42 10").command,
+            Command::ThreadGrowth { pid: 42, interval: 10 }
+        );
+        assert!(matches!(parser.parse("threadgrowth").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_threads_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("threads 42").command, Command::Threads { pid: 42 });
+        assert!(matches!(parser.parse("threads").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("threads abc").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_watch_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("watch 42").command, Command::Watch { pid: 42, interval: 1 });
+        assert_eq!(parser.parse("watch 42 5").command, Command::Watch { pid: 42, interval: 5 });
+        // A zero interval would redraw as fast as it could
+        assert_eq!(parser.parse("watch 42 0").command, Command::Watch { pid: 42, interval: 1 });
+        assert!(matches!(parser.parse("watch").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("watch abc").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_list_filters() {
+        let parser = CommandParser::new();
+        if let Command::ListProcesses { filter, exclude, .. } =
+            parser.parse("ps --filter nginx* --exclude *master*").command
+        {
+            assert_eq!(filter, Some("nginx*".to_string()));
+            assert_eq!(exclude, Some("*master*".to_string()));
+        } else {
+            panic!("Expected ListProcesses command");
+        }
+    }
+
+    #[test]
+    fn test_parse_min_usage_flags() {
+        let parser = CommandParser::new();
+        assert!(matches!(
+            parser.parse("ps --min-cpu 2.5 --min-mem 10240").command,
+            Command::ListProcesses { min_cpu: Some(2.5), min_mem: Some(10240), .. }
+        ));
+        assert!(matches!(
+            parser.parse("ps --min-cpu=1").command,
+            Command::ListProcesses { min_cpu: Some(1.0), min_mem: None, .. }
+        ));
+        assert!(matches!(
+            parser.parse("ps --min-mem lots").command,
+            Command::ListProcesses { min_cpu: None, min_mem: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_churn_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("churn").command, Command::Churn { interval: 1, names: false, utc: false });
+        assert_eq!(
+            parser.parse("churn 3 --names").command,
+            Command::Churn { interval: 3, names: true, utc: false }
+        );
+        assert_eq!(
+            parser.parse("churn --utc").command,
+            Command::Churn { interval: 1, names: false, utc: true }
+        );
+    }
+
+    #[test]
+    fn test_parse_all_threads_flag() {
+        let parser = CommandParser::new();
+        assert!(matches!(
+            parser.parse("ps --all-threads").command,
+            Command::ListProcesses { all_threads: true, .. }
+        ));
+        assert!(matches!(
+            parser.parse("ps -a").command,
+            Command::ListProcesses { all_threads: false, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_format_flag() {
+        let parser = CommandParser::new();
+        assert!(matches!(
+            parser.parse("ps").command,
+            Command::ListProcesses { format: OutputFormat::Table, .. }
+        ));
+        assert!(matches!(
+            parser.parse("ps --format json").command,
+            Command::ListProcesses { format: OutputFormat::Json { pretty: false }, .. }
+        ));
+        assert!(matches!(
+            parser.parse("ps -a --format CSV").command,
+            Command::ListProcesses { format: OutputFormat::Csv, all: true, .. }
+        ));
+        assert!(matches!(parser.parse("ps --format xml").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_pretty_flag() {
+        let parser = CommandParser::new();
+        let pretty = OutputFormat::Json { pretty: true };
+        assert!(matches!(
+            parser.parse("ps --format json --pretty").command,
+            Command::ListProcesses { format, .. } if format == pretty
+        ));
+        assert_eq!(
+            parser.parse("info 42 --pretty --format json").command,
+            Command::ProcessInfo { pid: 42, detailed: false, ancestry: false, format: pretty }
+        );
+        assert!(matches!(
+            parser.parse("monitor 1 --format json --pretty").command,
+            Command::Monitor { format, .. } if format == pretty
+        ));
+        assert!(matches!(
+            parser.parse("monitor --format csv").command,
+            Command::Monitor { format: OutputFormat::Csv, .. }
+        ));
+        // Only JSON has an indented form
+        assert!(matches!(parser.parse("ps --pretty").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("info 42 --format csv --pretty").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_cpushare_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("cpushare").command, Command::CpuShare { top: None });
+        assert_eq!(
+            parser.parse("cpushare --top 5").command,
+            Command::CpuShare { top: Some(5) }
+        );
+    }
+
+    #[test]
+    fn test_parse_swapping_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("swapping").command, Command::Swapping { top: None });
+        assert_eq!(
+            parser.parse("swapping --top 3").command,
+            Command::Swapping { top: Some(3) }
+        );
+    }
+
+    #[test]
+    fn test_parse_waitfor_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("waitfor nginx --timeout 30").command,
+            Command::WaitFor { name: "nginx".to_string(), timeout: Some(30) }
+        );
+        assert_eq!(
+            parser.parse("waitfor nginx").command,
+            Command::WaitFor { name: "nginx".to_string(), timeout: None }
+        );
+        assert!(matches!(parser.parse("waitfor").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_monitor_thresholds() {
+        let parser = CommandParser::new();
+        assert!(matches!(
+            parser.parse("monitor 2 --min-cpu 5.0 --min-mem 100000").command,
+            Command::Monitor { interval: 2, min_cpu: Some(5.0), min_mem: Some(100000), .. }
+        ));
+        assert!(matches!(
+            parser.parse("monitor --min-cpu 1").command,
+            Command::Monitor { interval: 2, min_cpu: Some(1.0), min_mem: None, .. }
+        ));
+        assert!(matches!(
+            parser.parse("monitor 3").command,
+            Command::Monitor { interval: 3, min_cpu: None, min_mem: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_monitor_top() {
+        let parser = CommandParser::new();
+        assert!(matches!(
+            parser.parse("monitor 1 --top 10 --sort mem").command,
+            Command::Monitor { interval: 1, top: Some(10), sort_by: Some(key), .. } if key == "mem"
+        ));
+        assert!(matches!(parser.parse("monitor --top 5").command, Command::Monitor { top: Some(5), .. }));
+        assert!(matches!(parser.parse("monitor").command, Command::Monitor { top: None, .. }));
+    }
+
+    #[test]
+    fn test_parse_monitor_command() {
+        let parser = CommandParser::new();
+        let monitor = |interval, both, count, no_clear| Command::Monitor {
+            interval,
+            both,
+            count,
+            no_clear,
+            sort_by: None,
+            utc: false,
+            events: false,
+            only_changed: false,
+            adaptive: None,
+            min_cpu: None,
+            min_mem: None,
+            top: None,
+            format: OutputFormat::Table,
+        };
+        assert_eq!(parser.parse("monitor").command, monitor(2, false, None, false));
+        assert_eq!(parser.parse("monitor 5 --both").command, monitor(5, true, None, false));
+        assert_eq!(parser.parse("monitor 1 --count 10").command, monitor(1, false, Some(10), false));
+        assert_eq!(
+            parser.parse("monitor 1 --no-clear --count 3").command,
+            monitor(1, false, Some(3), true)
+        );
+        assert!(matches!(
+            parser.parse("monitor 1 --sort ctxsw").command,
+            Command::Monitor { sort_by: Some(key), .. } if key == "ctxsw"
+        ));
+        assert!(matches!(
+            parser.parse("monitor 1 --no-clear --utc").command,
+            Command::Monitor { no_clear: true, utc: true, .. }
+        ));
+        assert!(matches!(
+            parser.parse("monitor --events").command,
+            Command::Monitor { interval: 2, events: true, .. }
+        ));
+        assert!(matches!(
+            parser.parse("monitor 1 --only-changed").command,
+            Command::Monitor { only_changed: true, events: false, .. }
+        ));
+        assert!(matches!(
+            parser.parse("monitor --adaptive").command,
+            Command::Monitor { adaptive: Some((1, 10)), .. }
+        ));
+        assert!(matches!(
+            parser.parse("monitor --adaptive --min-interval 2 --max-interval 30").command,
+            Command::Monitor { adaptive: Some((2, 30)), .. }
+        ));
+        // A maximum below the minimum collapses to a fixed interval
+        assert!(matches!(
+            parser.parse("monitor --adaptive --min-interval 5 --max-interval 3").command,
+            Command::Monitor { adaptive: Some((5, 5)), .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_both_flag() {
+        let parser = CommandParser::new();
+        assert!(matches!(
+            parser.parse("ps --both").command,
+            Command::ListProcesses { both: true, .. }
+        ));
+        assert!(matches!(
+            parser.parse("ps").command,
+            Command::ListProcesses { both: false, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_last_cpu_flag() {
+        let parser = CommandParser::new();
+        assert!(matches!(
+            parser.parse("ps --last-cpu").command,
+            Command::ListProcesses { last_cpu: true, both: false, .. }
+        ));
+        assert!(matches!(
+            parser.parse("ps -a").command,
+            Command::ListProcesses { last_cpu: false, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_top1_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("top1 cpu").command, Command::Top1 { resource: Resource::Cpu });
+        assert_eq!(parser.parse("top1 MEM").command, Command::Top1 { resource: Resource::Mem });
+        assert_eq!(parser.parse("top1 io").command, Command::Top1 { resource: Resource::Io });
+        assert!(matches!(parser.parse("top1 disk").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("top1").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_buffer_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.parse("buffer").command, Command::Buffer { mode: None });
+        assert_eq!(parser.parse("buffer line").command, Command::Buffer { mode: Some(Buffering::Line) });
+        assert_eq!(parser.parse("buffer Block").command, Command::Buffer { mode: Some(Buffering::Block) });
+        assert!(matches!(parser.parse("buffer full").command, Command::Unknown(_)));
+    }
+
+    fn words(input: &str) -> Vec<String> {
+        tokenize(input).unwrap().into_iter().map(|token| token.text).collect()
+    }
+
+    #[test]
+    fn test_expand_short_flags() {
+        let expand = |input: &str| expand_short_flags(tokenize(input).unwrap()).unwrap();
+        assert_eq!(expand("ps -au root"), vec!["ps", "-a", "-u", "root"]);
+        assert_eq!(expand("kill 12 -9"), vec!["kill", "12", "-9"]);
+        assert_eq!(expand("ps --all -a"), vec!["ps", "--all", "-a"]);
+        assert_eq!(expand("search '-abc'"), vec!["search", "-abc"]);
+        assert_eq!(
+            expand_short_flags(tokenize("ps -ax").unwrap()),
+            Err("ps: unknown flag -x in -ax".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tokenize_quotes() {
+        assert_eq!(words("search \"My App\" -e"), vec!["search", "My App", "-e"]);
+        assert_eq!(words("search 'Web Content'"), vec!["search", "Web Content"]);
+        assert_eq!(words("a\"b c\"d"), vec!["ab cd"]);
+        assert_eq!(words("search \"\""), vec!["search", ""]);
+        assert!(tokenize("search \"My App").is_err());
+        assert!(tokenize("search 'My App").is_err());
+    }
+
+    #[test]
+    fn test_tokenize_escapes() {
+        assert_eq!(words(r#"search "say \"hi\"""#), vec!["search", "say \"hi\""]);
+        assert_eq!(words(r#"search My\ App"#), vec!["search", "My App"]);
+        // Backslashes are literal inside single quotes
+        assert_eq!(words(r#"search 'a\b'"#), vec!["search", r"a\b"]);
+    }
+
+    #[test]
+    fn test_parse_quoted_arguments() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("search \"Web Content\" --count").command,
+            Command::SearchProcess { name: "Web Content".to_string(), exact: false, count: true }
+        );
+        assert!(matches!(parser.parse("search \"Web").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_combined_short_flags() {
+        let parser = CommandParser::new();
+        if let Command::ListProcesses { all, user, .. } = parser.parse("ps -au root").command {
+            assert!(all);
+            assert_eq!(user, Some("root".to_string()));
+        } else {
+            panic!("Expected ListProcesses command");
+        }
+
+        if let Command::ListProcesses { all, sort_by, .. } = parser.parse("list -as pss").command {
+            assert!(all);
+            assert_eq!(sort_by, Some("pss".to_string()));
+        } else {
+            panic!("Expected ListProcesses command");
+        }
+
+        assert_eq!(
+            parser.parse("info 42 -d").command,
+            Command::ProcessInfo { pid: 42, detailed: true, ancestry: false, format: OutputFormat::Table }
+        );
+
+        assert!(matches!(
+            parser.parse("monitor 3 -bs mem").command,
+            Command::Monitor { interval: 3, both: true, sort_by: Some(key), .. } if key == "mem"
+        ));
+        assert_eq!(
+            parser.parse("search -ec nginx").command,
+            Command::SearchProcess { name: "nginx".to_string(), exact: true, count: true }
+        );
+        assert_eq!(
+            parser.parse("find nginx -ce").command,
+            Command::SearchProcess { name: "nginx".to_string(), exact: true, count: true }
+        );
+
+        // A letter the command doesn't know is reported, not skipped
+        assert_eq!(
+            parser.parse("ps -ax").command,
+            Command::Unknown("ps: unknown flag -x in -ax".to_string())
+        );
+        assert!(matches!(parser.parse("search -ex nginx").command, Command::Unknown(_)));
+        assert!(matches!(parser.parse("monitor -bz").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_info_command() {
+        let parser = CommandParser::new();
+        assert_eq!(
+            parser.parse("info 42").command,
+            Command::ProcessInfo { pid: 42, detailed: false, ancestry: false, format: OutputFormat::Table }
+        );
+        assert_eq!(
+            parser.parse("info 42 --ancestry").command,
+            Command::ProcessInfo { pid: 42, detailed: false, ancestry: true, format: OutputFormat::Table }
+        );
+        assert!(matches!(parser.parse("info abc").command, Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_help() {
         let parser = CommandParser::new();
         let result = parser.parse("help");
         assert!(matches!(result.command, Command::Help));
     }
+
+    #[test]
+    fn test_suggest_command() {
+        let parser = CommandParser::new();
+        assert_eq!(parser.suggest("pss"), Some("ps"));
+        assert_eq!(parser.suggest("kil 1234"), Some("kill"));
+        assert_eq!(parser.suggest("MONITR"), Some("monitor"));
+        assert_eq!(parser.suggest("xyzzy"), None);
+        // A real command isn't a typo, even when its arguments are wrong
+        assert_eq!(parser.suggest("info abc"), None);
+        assert_eq!(parser.suggest(""), None);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("ps", "ps"), 0);
+        assert_eq!(edit_distance("pss", "ps"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "kill"), 4);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,287 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+use crate::proc_reader::list_pids;
+
+// From linux/connector.h and linux/cn_proc.h
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_EVENT_FORK: u32 = 0x0000_0001;
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+// struct nlmsghdr, struct cn_msg, and the proc_event header (what, cpu, timestamp)
+const NLMSG_HEADER_LEN: usize = 16;
+const CN_MSG_LEN: usize = 20;
+const EVENT_HEADER_LEN: usize = 16;
+const EVENT_DATA: usize = NLMSG_HEADER_LEN + CN_MSG_LEN + EVENT_HEADER_LEN;
+
+#[derive(Debug, PartialEq)]
+pub enum ProcEvent {
+    Started(u32),
+    // Same PID, new program: its name and owner may have changed
+    Exec(u32),
+    Exited(u32),
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buf.get(offset..offset + 4)?;
+    Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+}
+
+// Decode one connector message. Only whole processes are reported: threads
+// also fork and exit, but their TID differs from their thread group ID.
+pub fn parse_event(buf: &[u8]) -> Option<ProcEvent> {
+    let what = read_u32(buf, NLMSG_HEADER_LEN + CN_MSG_LEN)?;
+    match what {
+        // parent_pid, parent_tgid, child_pid, child_tgid
+        PROC_EVENT_FORK => {
+            let pid = read_u32(buf, EVENT_DATA + 8)?;
+            let tgid = read_u32(buf, EVENT_DATA + 12)?;
+            (pid == tgid).then_some(ProcEvent::Started(pid))
+        }
+        // process_pid, process_tgid
+        PROC_EVENT_EXEC => {
+            let pid = read_u32(buf, EVENT_DATA)?;
+            let tgid = read_u32(buf, EVENT_DATA + 4)?;
+            (pid == tgid).then_some(ProcEvent::Exec(pid))
+        }
+        // process_pid, process_tgid, exit_code, exit_signal
+        PROC_EVENT_EXIT => {
+            let pid = read_u32(buf, EVENT_DATA)?;
+            let tgid = read_u32(buf, EVENT_DATA + 4)?;
+            (pid == tgid).then_some(ProcEvent::Exited(pid))
+        }
+        _ => None,
+    }
+}
+
+// The listen request: a netlink header around a connector message whose
+// payload is PROC_CN_MCAST_LISTEN
+fn listen_message() -> Vec<u8> {
+    let len = NLMSG_HEADER_LEN + CN_MSG_LEN + 4;
+    let mut msg = Vec::with_capacity(len);
+    msg.extend_from_slice(&(len as u32).to_ne_bytes());
+    msg.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+    msg.extend_from_slice(&0u16.to_ne_bytes()); // flags
+    msg.extend_from_slice(&0u32.to_ne_bytes()); // seq
+    msg.extend_from_slice(&0u32.to_ne_bytes()); // sender port, filled in by the kernel
+    msg.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+    msg.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes()); // seq
+    msg.extend_from_slice(&0u32.to_ne_bytes()); // ack
+    msg.extend_from_slice(&4u16.to_ne_bytes()); // payload length
+    msg.extend_from_slice(&0u16.to_ne_bytes()); // flags
+    msg.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+    msg
+}
+
+// A subscription to process events. The socket is non-blocking and is only
+// drained when asked, so events queue up in the kernel between refreshes.
+pub struct ProcEvents {
+    socket: OwnedFd,
+}
+
+// Subscribe to process start/exit notifications from the kernel's netlink proc
+// connector. /proc itself can't be watched, since procfs emits no inotify
+// events. Needs CAP_NET_ADMIN (in practice, root).
+pub fn subscribe() -> io::Result<ProcEvents> {
+    // SAFETY: plain socket syscalls on a descriptor we own; every pointer passed
+    // refers to a live local of the stated length
+    unsafe {
+        let fd = libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            libc::NETLINK_CONNECTOR,
+        );
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let socket = OwnedFd::from_raw_fd(fd);
+
+        let mut addr: libc::sockaddr_nl = mem::zeroed();
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        // nl_pid 0 lets the kernel pick a unique port ID
+        addr.nl_groups = CN_IDX_PROC;
+        let addr_len = mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+        if libc::bind(fd, &addr as *const _ as *const libc::sockaddr, addr_len) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let msg = listen_message();
+        if libc::send(fd, msg.as_ptr() as *const libc::c_void, msg.len(), 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(ProcEvents { socket })
+    }
+}
+
+impl ProcEvents {
+    // Every event queued since the last call. Fails with ENOBUFS when the kernel
+    // dropped events because they arrived faster than we read them.
+    pub fn drain(&mut self) -> io::Result<Vec<ProcEvent>> {
+        let mut events = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            // SAFETY: `buf` is valid for writes of its full length
+            let received = unsafe {
+                libc::recv(
+                    self.socket.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if received < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::WouldBlock {
+                    return Ok(events);
+                }
+                return Err(err);
+            }
+            events.extend(parse_event(&buf[..received as usize]));
+        }
+    }
+}
+
+// Rescan /proc this often even with events, to recover from anything missed
+const FULL_RESCAN_EVERY: u32 = 10;
+
+// What one refresh has to read. After a full rescan every PID needs a full
+// read; between rescans only the `changed` ones (started or exec'd since the
+// last refresh) do, and the rest can be updated from what's already known.
+pub struct PidUpdate {
+    pub pids: Vec<u32>,
+    pub full: bool,
+    pub changed: BTreeSet<u32>,
+}
+
+impl PidUpdate {
+    pub fn needs_full_read(&self, pid: u32) -> bool {
+        self.full || self.changed.contains(&pid)
+    }
+}
+
+// The set of live PIDs for a refresh loop. With events, it's patched from
+// start/exec/exit notifications and only rebuilt from /proc every few
+// refreshes or after the kernel drops events; without them every call lists
+// /proc and is a full rescan.
+pub struct PidTracker {
+    events: Option<ProcEvents>,
+    pids: BTreeSet<u32>,
+    since_rescan: u32,
+}
+
+impl PidTracker {
+    pub fn new(events: Option<ProcEvents>) -> PidTracker {
+        PidTracker { events, pids: BTreeSet::new(), since_rescan: FULL_RESCAN_EVERY }
+    }
+
+    pub fn pids(&mut self) -> PidUpdate {
+        let mut rescan = self.since_rescan >= FULL_RESCAN_EVERY;
+        let mut changed = BTreeSet::new();
+        if let Some(events) = self.events.as_mut() {
+            match events.drain() {
+                Ok(events) => apply_events(&mut self.pids, &mut changed, events),
+                // Events were lost, so the set can't be trusted
+                Err(_) => rescan = true,
+            }
+        } else {
+            rescan = true;
+        }
+
+        if rescan {
+            self.pids = list_pids().unwrap_or_default().into_iter().collect();
+            self.since_rescan = 0;
+        }
+        self.since_rescan += 1;
+        PidUpdate { pids: self.pids.iter().copied().collect(), full: rescan, changed }
+    }
+}
+
+// Patch the PID set from events, collecting the live PIDs that started or
+// exec'd into `changed`
+pub fn apply_events(pids: &mut BTreeSet<u32>, changed: &mut BTreeSet<u32>, events: Vec<ProcEvent>) {
+    for event in events {
+        match event {
+            ProcEvent::Started(pid) | ProcEvent::Exec(pid) => {
+                pids.insert(pid);
+                changed.insert(pid);
+            }
+            ProcEvent::Exited(pid) => {
+                pids.remove(&pid);
+                changed.remove(&pid);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A connector message carrying a proc_event with the given type and data words
+    fn message(what: u32, data: [u32; 4]) -> Vec<u8> {
+        let mut buf = vec![0u8; NLMSG_HEADER_LEN + CN_MSG_LEN];
+        buf.extend_from_slice(&what.to_ne_bytes());
+        buf.extend_from_slice(&[0u8; 12]); // cpu and timestamp
+        for word in data {
+            buf.extend_from_slice(&word.to_ne_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_parse_event() {
+        assert_eq!(
+            parse_event(&message(PROC_EVENT_FORK, [1, 1, 500, 500])),
+            Some(ProcEvent::Started(500))
+        );
+        assert_eq!(
+            parse_event(&message(PROC_EVENT_EXIT, [500, 500, 0, 17])),
+            Some(ProcEvent::Exited(500))
+        );
+        assert_eq!(
+            parse_event(&message(PROC_EVENT_EXEC, [500, 500, 0, 0])),
+            Some(ProcEvent::Exec(500))
+        );
+        // A new thread of process 500, and a non-leader thread's exec
+        assert_eq!(parse_event(&message(PROC_EVENT_FORK, [500, 500, 501, 500])), None);
+        assert_eq!(parse_event(&message(PROC_EVENT_EXEC, [501, 500, 0, 0])), None);
+        // A UID change, which isn't followed
+        assert_eq!(parse_event(&message(0x4, [500, 500, 0, 0])), None);
+        assert_eq!(parse_event(&[0u8; 8]), None);
+    }
+
+    #[test]
+    fn test_apply_events() {
+        let mut pids: BTreeSet<u32> = [1, 2, 3].into_iter().collect();
+        let mut changed = BTreeSet::new();
+        apply_events(
+            &mut pids,
+            &mut changed,
+            vec![
+                ProcEvent::Started(7),
+                ProcEvent::Exited(2),
+                ProcEvent::Started(8),
+                ProcEvent::Exec(3),
+                ProcEvent::Exited(8),
+            ],
+        );
+        assert_eq!(pids.into_iter().collect::<Vec<_>>(), vec![1, 3, 7]);
+        // 8 came and went, so there's nothing to read for it
+        assert_eq!(changed.into_iter().collect::<Vec<_>>(), vec![3, 7]);
+    }
+
+    #[test]
+    fn test_needs_full_read() {
+        let update = PidUpdate { pids: vec![1, 3, 7], full: false, changed: [7].into_iter().collect() };
+        assert!(update.needs_full_read(7));
+        assert!(!update.needs_full_read(1));
+        let rescan = PidUpdate { full: true, ..update };
+        assert!(rescan.needs_full_read(1));
+    }
+}
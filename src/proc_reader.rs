@@ -1,51 +1,251 @@
+use std::fmt;
 use std::fs;
-use std::io::{self, BufRead};
+use std::io;
 use std::path::Path;
-use libc;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
+use serde::Serialize;
 
+use crate::system::read_meminfo;
+use crate::users;
+
+// Why reading a process from /proc failed, so callers can react differently to
+// a process that exited, one we may not inspect, and data we couldn't make sense of
 #[derive(Debug)]
+pub enum ProcError {
+    NotFound,
+    PermissionDenied,
+    Parse(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for ProcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcError::NotFound => write!(f, "no such process"),
+            ProcError::PermissionDenied => write!(f, "permission denied"),
+            ProcError::Parse(msg) => write!(f, "malformed /proc data: {}", msg),
+            ProcError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProcError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ProcError {
+    fn from(e: io::Error) -> Self {
+        // A process exiting mid-read can surface as ESRCH rather than ENOENT
+        if e.raw_os_error() == Some(libc::ESRCH) {
+            return ProcError::NotFound;
+        }
+        match e.kind() {
+            io::ErrorKind::NotFound => ProcError::NotFound,
+            io::ErrorKind::PermissionDenied => ProcError::PermissionDenied,
+            _ => ProcError::Io(e),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct ProcessMetrics {
     pub pid: u32,
     pub comm: String,
+    // Owner's user name from the real UID, or the bare UID without a passwd
+    // entry; "?" when status has no Uid line
     pub user: String,
+    pub uid: Option<u32>,
     pub cpu_time: f64,
     pub mem_usage: u64,
+    // RSS as a percentage of total system memory
+    pub mem_percent: f64,
     pub io_read_bytes: u64,
     pub io_write_bytes: u64,
+    // The io file belongs to another user and we may not read it, so the I/O
+    // bytes above are unknown rather than zero
+    pub io_denied: bool,
+    // Total user + system CPU time consumed since the process started
+    pub cpu_seconds: f64,
+    // Proportional set size in KB; only filled in when requested since smaps is expensive
+    pub pss: Option<u64>,
+    pub nice: i64,
+    // Wall-clock seconds since the process started
+    pub elapsed_seconds: f64,
+    // CPU the process was last scheduled on
+    pub last_cpu: Option<u32>,
+    // Cumulative context switches: voluntary ones happen when the process blocks,
+    // nonvoluntary ones when the scheduler preempts it
+    pub voluntary_ctxt_switches: u64,
+    pub nonvoluntary_ctxt_switches: u64,
+    // State character from stat (R, S, D, Z, ...)
+    pub state: char,
+    // Parent PID; 0 for PID 1 and kernel threads started by the kernel itself
+    pub ppid: u32,
+}
+
+// List the PIDs of all processes currently present in /proc
+pub fn list_pids() -> io::Result<Vec<u32>> {
+    let mut pids = Vec::new();
+    for entry in fs::read_dir("/proc")? {
+        let entry = entry?;
+        if let Ok(pid) = entry.file_name().to_str().unwrap_or("").parse::<u32>() {
+            pids.push(pid);
+        }
+    }
+    Ok(pids)
+}
+
+#[derive(Debug, Clone)]
+pub struct ThreadMetrics {
+    pub pid: u32,
+    pub tid: u32,
+    pub comm: String,
+    pub state: char,
+}
+
+// List the thread IDs of a process from /proc/[pid]/task
+pub fn list_tids(pid: u32) -> Result<Vec<u32>, ProcError> {
+    let mut tids = Vec::new();
+    for entry in fs::read_dir(format!("/proc/{}/task", pid))? {
+        let entry = entry?;
+        if let Ok(tid) = entry.file_name().to_str().unwrap_or("").parse::<u32>() {
+            tids.push(tid);
+        }
+    }
+    tids.sort_unstable();
+    Ok(tids)
+}
+
+// Name and state of one thread, from /proc/[pid]/task/[tid]/stat
+pub fn get_thread_metrics(pid: u32, tid: u32) -> Result<ThreadMetrics, ProcError> {
+    let (comm, state) = read_name_and_state(&format!("/proc/{}/task/{}", pid, tid))?;
+    Ok(ThreadMetrics { pid, tid, comm, state })
+}
+
+// Read metrics for every process, skipping the ones that can't be read
+// (typically because they exited between listing /proc and reading them)
+pub fn scan_processes() -> Vec<ProcessMetrics> {
+    scan_processes_checked().0
+}
+
+// Like `scan_processes`, but also hands back the failures other than a process
+// having exited, which point at something worth reporting
+pub fn scan_processes_checked() -> (Vec<ProcessMetrics>, Vec<(u32, ProcError)>) {
+    let mut processes = Vec::new();
+    let mut failures = Vec::new();
+    for pid in list_pids().unwrap_or_default() {
+        match get_process_metrics(pid) {
+            Ok(metrics) => processes.push(metrics),
+            Err(ProcError::NotFound) => {}
+            Err(e) => failures.push((pid, e)),
+        }
+    }
+    (processes, failures)
 }
 
 // Helper to read the entire contents of a file as String
-fn read_file(path: &str) -> io::Result<String> {
-    fs::read_to_string(path)
+fn read_file(path: &str) -> Result<String, ProcError> {
+    Ok(fs::read_to_string(path)?)
 }
 
-fn parse_stat(pid: u32) -> io::Result<(String, f64)> {
-    let stat_path = format!("/proc/{}/stat", pid);
-    let stat_content = fs::read_to_string(&stat_path)?;
-    let parts: Vec<&str> = stat_content.split_whitespace().collect();
+// Values extracted from /proc/[pid]/stat
+struct StatFields {
+    comm: String,
+    cpu_percent: f64,
+    cpu_seconds: f64,
+    elapsed_seconds: f64,
+    nice: i64,
+    last_cpu: Option<u32>,
+    state: char,
+    ppid: u32,
+    // Resident memory from stat's rss, the same count as VmRSS in status
+    rss_kb: Option<u64>,
+}
+
+// The raw counters parse_stat needs from a stat line
+#[derive(Debug, PartialEq)]
+struct StatLine {
+    comm: String,
+    state: char,
+    ppid: u32,
+    utime: u64,
+    stime: u64,
+    nice: i64,
+    starttime: u64,
+    // rss (field 24), in pages
+    rss: Option<u64>,
+    processor: Option<u32>,
+}
+
+// Field `n` (1-based, as in proc(5)) of a stat line, given the fields after
+// the comm; those start at field 3 (state)
+fn stat_field<T: FromStr>(rest: &[&str], n: usize) -> Result<T, ProcError> {
+    let field = rest
+        .get(n - 3)
+        .ok_or_else(|| ProcError::Parse(format!("stat line has no field {}", n)))?;
+    field
+        .parse()
+        .map_err(|_| ProcError::Parse(format!("stat field {} is not a number: {}", n, field)))
+}
+
+// Split a stat line after the last ')', since the comm before it may contain
+// spaces and parens and would otherwise shift every later field
+fn parse_stat_line(stat_content: &str) -> Result<StatLine, ProcError> {
+    let (comm, state) = parse_comm_and_state(stat_content)?;
+    let (_, rest) = stat_content
+        .rsplit_once(')')
+        .ok_or_else(|| ProcError::Parse("stat line has no (comm) field".to_string()))?;
+    let rest: Vec<&str> = rest.split_whitespace().collect();
+    Ok(StatLine {
+        comm,
+        state,
+        // ppid (field 4)
+        ppid: stat_field(&rest, 4)?,
+        // utime and stime (fields 14 and 15)
+        utime: stat_field(&rest, 14)?,
+        stime: stat_field(&rest, 15)?,
+        // nice (field 19), from 19 (lowest priority) to -20 (highest)
+        nice: stat_field(&rest, 19)?,
+        // starttime (field 22)
+        starttime: stat_field(&rest, 22)?,
+        // rss (field 24), resident pages
+        rss: stat_field(&rest, 24).ok(),
+        // processor (field 39), the CPU the process last ran on; older
+        // kernels end the line before it
+        processor: stat_field(&rest, 39).ok(),
+    })
+}
 
-    // Process name
-    let comm = parts[1].trim_matches('(').trim_matches(')');
+fn parse_stat(pid: u32) -> Result<StatFields, ProcError> {
+    let stat_path = format!("/proc/{}/stat", pid);
+    let stat = stat_fields(&read_file(&stat_path)?)?;
 
-    // utime and stime (fields 14 and 15)
-    let utime = parts[13].parse::<u64>().unwrap_or(0);
-    let stime = parts[14].parse::<u64>().unwrap_or(0);
+    // Process name, preferably from the comm file (see read_comm)
+    let comm = read_comm(&format!("/proc/{}", pid)).unwrap_or(stat.comm);
+    Ok(StatFields { comm, ..stat })
+}
 
-    // starttime (field 22)
-    let starttime = parts[21].parse::<u64>().unwrap_or(0);
+// StatFields from a stat line alone, with the name as stat gives it
+fn stat_fields(stat_content: &str) -> Result<StatFields, ProcError> {
+    let StatLine { comm, state, ppid, utime, stime, nice, starttime, rss, processor } = parse_stat_line(stat_content)?;
 
     // Get system ticks per second as f64
     let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as f64;
 
     // Get current system uptime in seconds
-    let uptime_content = fs::read_to_string("/proc/uptime")?;
+    let uptime_content = read_file("/proc/uptime")?;
     let uptime = uptime_content
         .split_whitespace()
         .next()
-        .unwrap()
-        .parse::<f64>()
-        .unwrap();
+        .and_then(|field| field.parse::<f64>().ok())
+        .ok_or_else(|| ProcError::Parse("unreadable /proc/uptime".to_string()))?;
 
     // Calculate elapsed time (seconds) since process started
     let elapsed_seconds = uptime - (starttime as f64 / ticks_per_sec);
@@ -60,24 +260,230 @@ fn parse_stat(pid: u32) -> io::Result<(String, f64)> {
         0.0
     };
 
-    Ok((comm.to_string(), percent_cpu))
+    Ok(StatFields {
+        comm,
+        cpu_percent: percent_cpu,
+        cpu_seconds: total_cpu_time_seconds,
+        elapsed_seconds,
+        nice,
+        last_cpu: processor,
+        state,
+        ppid,
+        rss_kb: rss.map(pages_to_kb),
+    })
+}
+
+// A count of memory pages, such as stat's rss, in KB
+fn pages_to_kb(pages: u64) -> u64 {
+    // SAFETY: sysconf only reads a system constant
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+    pages * page_size / 1024
+}
+
+// The name from the comm file of a /proc/[pid] or /proc/[pid]/task/[tid]
+// directory. It holds the same name as the (comm) field of stat, but on its
+// own, so there's no parenthesised field to pick apart. None if the file can't
+// be read.
+fn read_comm(dir: &str) -> Option<String> {
+    let comm = fs::read_to_string(format!("{}/comm", dir)).ok()?;
+    Some(comm.strip_suffix('\n').unwrap_or(&comm).to_string())
 }
 
-// Parse /proc/[pid]/status for memory usage
-fn parse_status(pid: u32) -> io::Result<u64> {
+// Name and parent PID of a process. Reads only stat and comm, so it works for any process.
+pub fn get_parent(pid: u32) -> Result<(String, u32), ProcError> {
+    let dir = format!("/proc/{}", pid);
+    let stat = parse_stat_line(&read_file(&format!("{}/stat", dir))?)?;
+    Ok((read_comm(&dir).unwrap_or(stat.comm), stat.ppid))
+}
+
+// Read only the name and state character of a process. This is cheap and
+// doesn't block, unlike files such as status/io for a process stuck in D state.
+pub fn peek_state(pid: u32) -> Result<(String, char), ProcError> {
+    read_name_and_state(&format!("/proc/{}", pid))
+}
+
+// Name and state from the stat file in a process or thread directory, with the
+// name taken from the comm file when it can be read, as parse_stat does
+fn read_name_and_state(dir: &str) -> Result<(String, char), ProcError> {
+    let stat_content = read_file(&format!("{}/stat", dir))?;
+    let (comm, state) = parse_comm_and_state(&stat_content)?;
+    Ok((read_comm(dir).unwrap_or(comm), state))
+}
+
+fn parse_comm_and_state(stat_content: &str) -> Result<(String, char), ProcError> {
+    let open = stat_content.find('(');
+    let close = stat_content.rfind(')');
+    match (open, close) {
+        (Some(open), Some(close)) if open < close => {
+            let comm = stat_content[open + 1..close].to_string();
+            let state = stat_content[close + 1..]
+                .trim_start()
+                .chars()
+                .next()
+                .ok_or_else(|| ProcError::Parse("stat line has no state field".to_string()))?;
+            Ok((comm, state))
+        }
+        _ => Err(ProcError::Parse("stat line has no (comm) field".to_string())),
+    }
+}
+
+// Values extracted from /proc/[pid]/status
+struct StatusFields {
+    mem_usage: u64,
+    vm_size: u64,
+    uid: Option<u32>,
+    euid: Option<u32>,
+    voluntary_ctxt_switches: u64,
+    nonvoluntary_ctxt_switches: u64,
+    threads: u64,
+}
+
+// Parse /proc/[pid]/status for memory usage and owner
+fn parse_status(pid: u32) -> Result<StatusFields, ProcError> {
     let status_path = format!("/proc/{}/status", pid);
     let status = read_file(&status_path)?;
+    let mut fields = StatusFields {
+        mem_usage: 0,
+        vm_size: 0,
+        uid: None,
+        euid: None,
+        voluntary_ctxt_switches: 0,
+        nonvoluntary_ctxt_switches: 0,
+        threads: 0,
+    };
     for line in status.lines() {
         if line.starts_with("VmRSS:") {
             let parts: Vec<&str> = line.split_whitespace().collect();
-            return Ok(parts[1].parse::<u64>().unwrap_or(0));
+            fields.mem_usage = parts[1].parse::<u64>().unwrap_or(0);
+        } else if let Some(size) = line.strip_prefix("VmSize:") {
+            fields.vm_size = size.split_whitespace().next().and_then(|kb| kb.parse().ok()).unwrap_or(0);
+        } else if let Some(uids) = line.strip_prefix("Uid:") {
+            // Real, effective, saved and filesystem UIDs; the real one owns the process
+            let mut uids = uids.split_whitespace().map(|uid| uid.parse().ok());
+            fields.uid = uids.next().flatten();
+            fields.euid = uids.next().flatten();
+        } else if let Some(count) = line.strip_prefix("voluntary_ctxt_switches:") {
+            fields.voluntary_ctxt_switches = count.trim().parse().unwrap_or(0);
+        } else if let Some(count) = line.strip_prefix("nonvoluntary_ctxt_switches:") {
+            fields.nonvoluntary_ctxt_switches = count.trim().parse().unwrap_or(0);
+        } else if let Some(count) = line.strip_prefix("Threads:") {
+            fields.threads = count.trim().parse().unwrap_or(0);
+        }
+    }
+    Ok(fields)
+}
+
+// What `info -d` shows on top of ProcessMetrics
+#[derive(Debug)]
+pub struct DetailedInfo {
+    // Program and arguments; empty for kernel threads, which have no command line
+    pub cmdline: Vec<String>,
+    pub threads: u64,
+    // Virtual memory size in KB; 0 for kernel threads
+    pub vm_size: u64,
+    pub state: char,
+    // Wall-clock seconds since the process started
+    pub elapsed_seconds: f64,
+}
+
+pub fn get_detailed_info(pid: u32) -> Result<DetailedInfo, ProcError> {
+    let cmdline = parse_cmdline(&fs::read(format!("/proc/{}/cmdline", pid))?);
+    let status = parse_status(pid)?;
+    let stat = parse_stat(pid)?;
+    Ok(DetailedInfo {
+        cmdline,
+        threads: status.threads,
+        vm_size: status.vm_size,
+        state: stat.state,
+        elapsed_seconds: stat.elapsed_seconds,
+    })
+}
+
+// /proc/[pid]/cmdline holds the arguments separated and terminated by NULs.
+// Empty arguments in the middle are real and kept.
+fn parse_cmdline(raw: &[u8]) -> Vec<String> {
+    let raw = raw.strip_suffix(&[0]).unwrap_or(raw);
+    if raw.is_empty() {
+        return Vec::new();
+    }
+    raw.split(|&byte| byte == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+// The process's PID in each nested PID namespace, from the host's view inward,
+// so the last entry is the PID seen inside its container. None when the kernel
+// (before 4.1) doesn't report NSpid.
+pub fn get_namespaced_pids(pid: u32) -> Result<Option<Vec<u32>>, ProcError> {
+    let status = read_file(&format!("/proc/{}/status", pid))?;
+    Ok(parse_nspid(&status))
+}
+
+fn parse_nspid(status: &str) -> Option<Vec<u32>> {
+    let line = status.lines().find_map(|line| line.strip_prefix("NSpid:"))?;
+    line.split_whitespace().map(|pid| pid.parse().ok()).collect()
+}
+
+// Number of threads in the process. Like get_uid, only reads status.
+pub fn get_thread_count(pid: u32) -> Result<u64, ProcError> {
+    Ok(parse_status(pid)?.threads)
+}
+
+// Inode from an fd link that points to a socket, which reads `socket:[12345]`
+fn parse_socket_inode(link: &str) -> Option<u64> {
+    link.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+// Inodes of the sockets a process has open, found through /proc/[pid]/fd.
+// Listing another user's fds needs root.
+pub fn get_socket_inodes(pid: u32) -> Result<Vec<u64>, ProcError> {
+    let mut inodes = Vec::new();
+    for entry in fs::read_dir(format!("/proc/{}/fd", pid))? {
+        // An fd closed while we list the directory simply disappears
+        let Ok(link) = fs::read_link(entry?.path()) else {
+            continue;
+        };
+        if let Some(inode) = link.to_str().and_then(parse_socket_inode) {
+            inodes.push(inode);
         }
     }
-    Ok(0)
+    Ok(inodes)
+}
+
+// Kernel function the process is blocked in, from /proc/[pid]/wchan. Reads
+// "0" when it's running; kernels that hide addresses may also show "0".
+pub fn get_wchan(pid: u32) -> Result<String, ProcError> {
+    read_file(&format!("/proc/{}/wchan", pid))
+}
+
+// Real UID of a process's owner. Reads only status, so unlike
+// get_process_metrics it works for other users' processes too.
+pub fn get_uid(pid: u32) -> Result<Option<u32>, ProcError> {
+    Ok(parse_status(pid)?.uid)
+}
+
+// Real and effective UID of a process. They differ while it runs a setuid
+// binary or after it changed its own privileges. None if status has no Uid line.
+pub fn get_uids(pid: u32) -> Result<Option<(u32, u32)>, ProcError> {
+    let status = parse_status(pid)?;
+    Ok(status.uid.zip(status.euid))
+}
+
+// Whether a /proc tree has per-process I/O files. Kernels built without
+// CONFIG_TASK_IO_ACCOUNTING have none, not even for our own process.
+fn has_io_accounting(proc_root: &Path) -> bool {
+    proc_root.join("self/io").exists()
+}
+
+// Per-process I/O accounting, checked once: without it every io read would
+// fail, so metrics leave I/O at zero and tables show N/A instead
+pub fn io_accounting() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| has_io_accounting(Path::new("/proc")))
 }
 
 // Parse /proc/[pid]/io for I/O stats
-fn parse_io(pid: u32) -> io::Result<(u64, u64)> {
+fn parse_io(pid: u32) -> Result<(u64, u64), ProcError> {
     let io_path = format!("/proc/{}/io", pid);
     let io = read_file(&io_path)?;
     let mut read_bytes = 0;
@@ -94,22 +500,373 @@ fn parse_io(pid: u32) -> io::Result<(u64, u64)> {
     Ok((read_bytes, write_bytes))
 }
 
+#[derive(Debug, Default, PartialEq)]
+pub struct SmapsRollup {
+    pub rss: u64,
+    pub pss: u64,
+    pub shared_clean: u64,
+    pub shared_dirty: u64,
+    pub private_clean: u64,
+    pub private_dirty: u64,
+    pub swap: u64,
+}
+
+// Parse /proc/[pid]/smaps_rollup for the memory breakdown (all values in KB).
+// Only present on Linux 4.14+, and only readable for processes we may ptrace.
+pub fn get_smaps_rollup(pid: u32) -> Result<SmapsRollup, ProcError> {
+    parse_smaps_rollup(&read_file(&format!("/proc/{}/smaps_rollup", pid))?)
+}
+
+// The fields we show from smaps_rollup content: "Name:   value kB" lines after
+// a header naming the mapping. Fields missing from older kernels stay 0.
+fn parse_smaps_rollup(content: &str) -> Result<SmapsRollup, ProcError> {
+    let mut rollup = SmapsRollup::default();
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(name), Some(value)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let field = match name {
+            "Rss:" => &mut rollup.rss,
+            "Pss:" => &mut rollup.pss,
+            "Shared_Clean:" => &mut rollup.shared_clean,
+            "Shared_Dirty:" => &mut rollup.shared_dirty,
+            "Private_Clean:" => &mut rollup.private_clean,
+            "Private_Dirty:" => &mut rollup.private_dirty,
+            "Swap:" => &mut rollup.swap,
+            _ => continue,
+        };
+        *field = value
+            .parse()
+            .map_err(|_| ProcError::Parse(format!("smaps_rollup {} is not a number: {}", name, value)))?;
+    }
+    Ok(rollup)
+}
+
+// MemTotal in KB; it doesn't change while we run, so read it once
+fn total_memory_kb() -> u64 {
+    static TOTAL: OnceLock<u64> = OnceLock::new();
+    *TOTAL.get_or_init(|| read_meminfo().map(|info| info.kb("MemTotal")).unwrap_or(0))
+}
+
+fn percent_of_total_memory(kb: u64) -> f64 {
+    let total = total_memory_kb();
+    if total == 0 {
+        0.0
+    } else {
+        kb as f64 / total as f64 * 100.0
+    }
+}
+
+// Another user's io file needs privileges; that shouldn't hide the whole
+// process from scans (None: I/O unknown), but one that exited mid-read is
+// still an error and gets skipped
+fn readable_io(io: Result<(u64, u64), ProcError>) -> Result<Option<(u64, u64)>, ProcError> {
+    match io {
+        Ok(io) => Ok(Some(io)),
+        Err(ProcError::PermissionDenied) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
 // Combine all metrics above
-pub fn get_process_metrics(pid: u32) -> io::Result<ProcessMetrics> {
-    let (comm, cpu_time) = parse_stat(pid)?;
-    let mem_usage = parse_status(pid)?;
-    let (io_read_bytes, io_write_bytes) = parse_io(pid)?;
+pub fn get_process_metrics(pid: u32) -> Result<ProcessMetrics, ProcError> {
+    let stat = parse_stat(pid)?;
+    let status = parse_status(pid)?;
+    let mem_usage = status.mem_usage;
+    let io = if io_accounting() { readable_io(parse_io(pid))? } else { Some((0, 0)) };
+    let (io_read_bytes, io_write_bytes) = io.unwrap_or((0, 0));
 
-    // For user name, simplified (real code: get UID from /proc/[pid]/status and map to user)
-    let user = "user".to_string();
+    let user = status.uid.map(users::owner_name).unwrap_or_else(|| "?".to_string());
 
     Ok(ProcessMetrics {
         pid,
-        comm,
+        comm: stat.comm,
         user,
-        cpu_time,
+        uid: status.uid,
+        cpu_time: stat.cpu_percent,
         mem_usage,
+        mem_percent: percent_of_total_memory(mem_usage),
         io_read_bytes,
         io_write_bytes,
+        io_denied: io.is_none(),
+        cpu_seconds: stat.cpu_seconds,
+        pss: None,
+        nice: stat.nice,
+        elapsed_seconds: stat.elapsed_seconds,
+        last_cpu: stat.last_cpu,
+        voluntary_ctxt_switches: status.voluntary_ctxt_switches,
+        nonvoluntary_ctxt_switches: status.nonvoluntary_ctxt_switches,
+        state: stat.state,
+        ppid: stat.ppid,
     })
-}
\ No newline at end of file
+}
+
+// Bring metrics from an earlier get_process_metrics up to date from stat
+// alone: CPU time, resident memory, state, nice and parent change all the
+// time, while the name, owner, I/O and context switches are kept as they were.
+// Reads stat and uptime instead of get_process_metrics' five files, for
+// refreshes that already know which processes are new.
+pub fn refresh_process_metrics(metrics: &ProcessMetrics) -> Result<ProcessMetrics, ProcError> {
+    let stat = stat_fields(&read_file(&format!("/proc/{}/stat", metrics.pid))?)?;
+    let mem_usage = stat.rss_kb.unwrap_or(metrics.mem_usage);
+    Ok(ProcessMetrics {
+        cpu_time: stat.cpu_percent,
+        mem_usage,
+        mem_percent: percent_of_total_memory(mem_usage),
+        cpu_seconds: stat.cpu_seconds,
+        nice: stat.nice,
+        elapsed_seconds: stat.elapsed_seconds,
+        last_cpu: stat.last_cpu,
+        state: stat.state,
+        ppid: stat.ppid,
+        ..metrics.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_errors_map_to_proc_error() {
+        let not_found = ProcError::from(io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(not_found, ProcError::NotFound));
+
+        let exited = ProcError::from(io::Error::from_raw_os_error(libc::ESRCH));
+        assert!(matches!(exited, ProcError::NotFound));
+
+        let denied = ProcError::from(io::Error::from_raw_os_error(libc::EACCES));
+        assert!(matches!(denied, ProcError::PermissionDenied));
+
+        let other = ProcError::from(io::Error::from_raw_os_error(libc::EIO));
+        assert!(matches!(other, ProcError::Io(_)));
+    }
+
+    #[test]
+    fn test_denied_io_keeps_the_process() {
+        assert_eq!(readable_io(Ok((10, 20))).unwrap(), Some((10, 20)));
+        assert_eq!(readable_io(Err(ProcError::PermissionDenied)).unwrap(), None);
+        assert!(matches!(readable_io(Err(ProcError::NotFound)), Err(ProcError::NotFound)));
+    }
+
+    #[test]
+    fn test_parse_smaps_rollup() {
+        let content = "\
+55d4c7a4e000-7ffd5b9f8000 ---p 00000000 00:00 0                          [rollup]
+Rss:                4096 kB
+Pss:                1234 kB
+Pss_Anon:            800 kB
+Shared_Clean:       2048 kB
+Shared_Dirty:        512 kB
+Private_Clean:       256 kB
+Private_Dirty:      1280 kB
+Swap:                 64 kB
+SwapPss:              64 kB
+";
+        assert_eq!(
+            parse_smaps_rollup(content).unwrap(),
+            SmapsRollup {
+                rss: 4096,
+                pss: 1234,
+                shared_clean: 2048,
+                shared_dirty: 512,
+                private_clean: 256,
+                private_dirty: 1280,
+                swap: 64,
+            }
+        );
+        // An empty rollup (kernel threads have no mappings) is all zeros
+        assert_eq!(parse_smaps_rollup("").unwrap(), SmapsRollup::default());
+        assert!(matches!(parse_smaps_rollup("Rss: lots kB"), Err(ProcError::Parse(_))));
+    }
+
+    #[test]
+    fn test_refresh_own_metrics() {
+        let full = get_process_metrics(std::process::id()).unwrap();
+        let refreshed = refresh_process_metrics(&full).unwrap();
+        assert_eq!(refreshed.comm, full.comm);
+        assert_eq!(refreshed.uid, full.uid);
+        assert_eq!(refreshed.ppid, full.ppid);
+        assert!(refreshed.cpu_seconds >= full.cpu_seconds);
+        assert!(refreshed.elapsed_seconds >= full.elapsed_seconds);
+        // stat's rss and status' VmRSS are the same counter, a moment apart
+        assert!(refreshed.mem_usage > 0);
+        assert!(refreshed.mem_usage.abs_diff(full.mem_usage) < full.mem_usage / 2);
+
+        let gone = ProcessMetrics { pid: u32::MAX, ..full };
+        assert!(matches!(refresh_process_metrics(&gone), Err(ProcError::NotFound)));
+    }
+
+    #[test]
+    fn test_stat_rss_kb() {
+        // SAFETY: sysconf only reads a system constant
+        let page_kb = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64 / 1024;
+        assert_eq!(stat_fields(&fake_stat("a) b", 52)).unwrap().rss_kb, Some(24 * page_kb));
+        assert_eq!(stat_fields(&fake_stat("bash", 23)).unwrap().rss_kb, None);
+    }
+
+    #[test]
+    fn test_missing_process_is_not_found() {
+        // PIDs never reach u32::MAX (pid_max is at most 2^22)
+        assert!(matches!(get_process_metrics(u32::MAX), Err(ProcError::NotFound)));
+    }
+
+    #[test]
+    fn test_scan_finds_own_process() {
+        let (processes, failures) = scan_processes_checked();
+        assert!(processes.iter().any(|m| m.pid == std::process::id()));
+        // Exited processes are skipped, never reported
+        assert!(!failures.iter().any(|(_, e)| matches!(e, ProcError::NotFound)));
+    }
+
+    #[test]
+    fn test_malformed_stat_is_parse_error() {
+        assert!(matches!(parse_comm_and_state("1234 bash S"), Err(ProcError::Parse(_))));
+        assert!(matches!(parse_comm_and_state("1234 (bash)"), Err(ProcError::Parse(_))));
+        assert_eq!(
+            parse_comm_and_state("1234 (bash) S 1").unwrap(),
+            ("bash".to_string(), 'S')
+        );
+    }
+
+    // A stat line for `comm` with each later field holding its own number,
+    // except state (field 3), which is 'S'
+    fn fake_stat(comm: &str, last_field: usize) -> String {
+        let fields: Vec<String> = (4..=last_field).map(|n| n.to_string()).collect();
+        format!("1234 ({}) S {}", comm, fields.join(" "))
+    }
+
+    #[test]
+    fn test_parse_stat_line_with_odd_comm() {
+        let stat = parse_stat_line(&fake_stat("Web Content) x", 52)).unwrap();
+        assert_eq!(
+            stat,
+            StatLine {
+                comm: "Web Content) x".to_string(),
+                state: 'S',
+                ppid: 4,
+                utime: 14,
+                stime: 15,
+                nice: 19,
+                starttime: 22,
+                rss: Some(24),
+                processor: Some(39),
+            }
+        );
+    }
+
+    #[test]
+    fn test_short_stat_line_is_parse_error() {
+        // Cut off before starttime (field 22): an error, not a panic or a zero
+        assert!(matches!(parse_stat_line(&fake_stat("bash", 21)), Err(ProcError::Parse(_))));
+        assert!(matches!(parse_stat_line("1234 (bash) S"), Err(ProcError::Parse(_))));
+        assert!(parse_stat_line(&fake_stat("bash", 22)).is_ok());
+        // A field that isn't a number is a parse error too
+        let garbled = fake_stat("bash", 52).replacen(" 14 ", " x ", 1);
+        assert!(matches!(parse_stat_line(&garbled), Err(ProcError::Parse(_))));
+    }
+
+    #[test]
+    fn test_nice_after_parenthesized_comm() {
+        // A comm of "(a b)" puts a space and an extra ')' before the fields, so
+        // counting from the start of the line would read the wrong one as nice
+        let stat = fake_stat("(a b)", 52).replacen(" 19 ", " -5 ", 1);
+        assert!(stat.starts_with("1234 ((a b)) S 4 "));
+        let stat = parse_stat_line(&stat).unwrap();
+        assert_eq!(stat.comm, "(a b)");
+        assert_eq!(stat.nice, -5);
+        assert_eq!(stat.starttime, 22);
+    }
+
+    #[test]
+    fn test_comm_file_matches_stat() {
+        let pid = std::process::id();
+        let from_file = read_comm(&format!("/proc/{}", pid)).expect("own comm is readable");
+        let stat_content = read_file(&format!("/proc/{}/stat", pid)).unwrap();
+        let (from_stat, _) = parse_comm_and_state(&stat_content).unwrap();
+        assert_eq!(from_file, from_stat);
+        assert!(!from_file.ends_with('\n'));
+        // Both lookups that prefer the comm file agree with it
+        assert_eq!(peek_state(pid).unwrap().0, from_file);
+        assert_eq!(get_parent(pid).unwrap().0, from_file);
+        assert_eq!(read_comm(&format!("/proc/{}", u32::MAX)), None);
+    }
+
+    #[test]
+    fn test_thread_name_from_comm() {
+        let pid = std::process::id();
+        let worker = std::thread::Builder::new()
+            .name("lpm-worker".to_string())
+            .spawn(move || {
+                let tid = unsafe { libc::gettid() } as u32;
+                get_thread_metrics(pid, tid).unwrap()
+            })
+            .unwrap();
+        let thread = worker.join().unwrap();
+        assert_eq!(thread.comm, "lpm-worker");
+        assert_eq!(thread.state, 'R');
+    }
+
+    #[test]
+    fn test_own_uids() {
+        let (real, effective) = get_uids(std::process::id()).unwrap().expect("status has a Uid line");
+        // SAFETY: getuid and geteuid cannot fail
+        assert_eq!((real, effective), unsafe { (libc::getuid(), libc::geteuid()) });
+    }
+
+    #[test]
+    fn test_own_user_from_real_uid() {
+        let metrics = get_process_metrics(std::process::id()).unwrap();
+        // SAFETY: getuid cannot fail
+        let uid = unsafe { libc::getuid() };
+        assert_eq!(metrics.uid, Some(uid));
+        assert_eq!(metrics.user, users::owner_name(uid));
+        assert_ne!(metrics.user, "?");
+    }
+
+    #[test]
+    fn test_has_io_accounting() {
+        assert!(has_io_accounting(Path::new("/proc")));
+        assert!(!has_io_accounting(Path::new("/nonexistent")));
+        assert_eq!(io_accounting(), has_io_accounting(Path::new("/proc")));
+    }
+
+    #[test]
+    fn test_parse_socket_inode() {
+        assert_eq!(parse_socket_inode("socket:[48812]"), Some(48812));
+        assert_eq!(parse_socket_inode("pipe:[48812]"), None);
+        assert_eq!(parse_socket_inode("/dev/null"), None);
+    }
+
+    #[test]
+    fn test_parse_nspid() {
+        let status = "Name:\tnginx\nNSpid:\t4242\t7\nVmRSS:\t10 kB\n";
+        assert_eq!(parse_nspid(status), Some(vec![4242, 7]));
+        assert_eq!(parse_nspid("NSpid:\t1\n"), Some(vec![1]));
+        assert_eq!(parse_nspid("Name:\tnginx\nVmRSS:\t10 kB\n"), None);
+    }
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(parse_cmdline(b"/usr/sbin/sshd\0-D\0"), vec!["/usr/sbin/sshd", "-D"]);
+        assert_eq!(parse_cmdline(b"echo\0\0x\0"), vec!["echo", "", "x"]);
+        assert!(parse_cmdline(b"").is_empty());
+    }
+
+    #[test]
+    fn test_own_detailed_info() {
+        let info = get_detailed_info(std::process::id()).unwrap();
+        assert!(!info.cmdline.is_empty());
+        assert!(info.threads >= 1);
+        assert!(info.vm_size > 0);
+    }
+
+    #[test]
+    fn test_stat_line_without_processor() {
+        // Kernels before 2.2.8 end the line before processor (field 39)
+        let stat = parse_stat_line(&fake_stat("tmux: a) b", 38)).unwrap();
+        assert_eq!(stat.processor, None);
+        assert_eq!(stat.ppid, 4);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::proc_reader::ProcessMetrics;
+use crate::system::MemInfo;
+
+type ProcessValue = fn(&ProcessMetrics) -> f64;
+
+// Per-process metric families: name, type, help text and how to read the value
+const PROCESS_FAMILIES: [(&str, &str, &str, ProcessValue); 5] = [
+    ("process_cpu_percent", "gauge", "CPU usage averaged over the process lifetime.", |m| {
+        m.cpu_time
+    }),
+    ("process_cpu_seconds_total", "counter", "User and system CPU time consumed.", |m| {
+        m.cpu_seconds
+    }),
+    ("process_resident_memory_bytes", "gauge", "Resident set size.", |m| {
+        (m.mem_usage * 1024) as f64
+    }),
+    ("process_io_read_bytes_total", "counter", "Bytes read from storage.", |m| {
+        m.io_read_bytes as f64
+    }),
+    ("process_io_write_bytes_total", "counter", "Bytes written to storage.", |m| {
+        m.io_write_bytes as f64
+    }),
+];
+
+// Label values may contain anything, so backslashes, quotes and newlines are escaped
+pub fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn family_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+}
+
+// Render process and system metrics in the Prometheus text exposition format
+pub fn render(processes: &[ProcessMetrics], memory: &MemInfo) -> String {
+    let mut out = String::new();
+
+    for (name, kind, help, value) in PROCESS_FAMILIES {
+        family_header(&mut out, name, kind, help);
+        for metrics in processes {
+            out.push_str(&format!(
+                "{}{{pid=\"{}\",comm=\"{}\"}} {}\n",
+                name,
+                metrics.pid,
+                escape_label(&metrics.comm),
+                value(metrics)
+            ));
+        }
+    }
+
+    let system = [
+        ("system_memory_total_bytes", "Total usable memory.", memory.kb("MemTotal") * 1024),
+        (
+            "system_memory_available_bytes",
+            "Memory available for new work.",
+            memory.kb("MemAvailable") * 1024,
+        ),
+        ("system_processes", "Number of processes that could be read.", processes.len() as u64),
+    ];
+    for (name, help, value) in system {
+        family_header(&mut out, name, "gauge", help);
+        out.push_str(&format!("{} {}\n", name, value));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::parse_meminfo;
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("nginx"), "nginx");
+        assert_eq!(escape_label(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape_label("two\nlines"), "two\\nlines");
+    }
+
+    #[test]
+    fn test_render_exposition_format() {
+        let processes = vec![ProcessMetrics {
+            pid: 123,
+            comm: "we\"ird".to_string(),
+            cpu_time: 4.2,
+            mem_usage: 2,
+            ..Default::default()
+        }];
+        let memory = parse_meminfo("MemTotal: 16 kB\nMemAvailable: 8 kB\n");
+        let text = render(&processes, &memory);
+
+        assert!(text.contains("# TYPE process_cpu_percent gauge\n"));
+        assert!(text.contains("process_cpu_percent{pid=\"123\",comm=\"we\\\"ird\"} 4.2\n"));
+        assert!(text.contains("process_resident_memory_bytes{pid=\"123\",comm=\"we\\\"ird\"} 2048\n"));
+        assert!(text.contains("system_memory_available_bytes 8192\n"));
+        assert!(text.contains("system_processes 1\n"));
+
+        // Every line is a comment or `name[{labels}] value`
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            assert!(value.parse::<f64>().is_ok(), "bad value in {:?}", line);
+            let name = series.split('{').next().unwrap();
+            assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::parser::{command_flags, COMMANDS};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::env;
+use std::path::PathBuf;
+
+// Dotfile in $HOME that keeps command history between sessions
+const HISTORY_FILE: &str = ".lpm_history";
+
+// What the prompt produced: a line to parse, a cancelled line, or end of input
+pub enum Input {
+    Line(String),
+    Interrupted,
+    Eof,
+}
+
+// The interactive prompt: arrow-key recall, line editing and persistent history
+pub struct Prompt {
+    editor: Editor<CommandCompleter, DefaultHistory>,
+    history: Option<PathBuf>,
+}
+
+impl Prompt {
+    pub fn new() -> rustyline::Result<Self> {
+        let mut editor = Editor::new()?;
+        editor.set_helper(Some(CommandCompleter));
+        let history = history_path();
+        if let Some(path) = &history {
+            // A missing file just means this is the first session
+            let _ = editor.load_history(path);
+        }
+        Ok(Prompt { editor, history })
+    }
+
+    // Read one line; non-blank lines are added to history and written out straight
+    // away so a crash or a kill doesn't lose the session
+    pub fn read(&mut self, prompt: &str) -> Input {
+        match self.editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    let _ = self.editor.add_history_entry(line.as_str());
+                    if let Some(path) = &self.history {
+                        let _ = self.editor.append_history(path);
+                    }
+                }
+                Input::Line(line)
+            }
+            Err(ReadlineError::Interrupted) => Input::Interrupted,
+            Err(_) => Input::Eof,
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    let home = env::var_os("HOME").filter(|home| !home.is_empty())?;
+    Some(PathBuf::from(home).join(HISTORY_FILE))
+}
+
+// Tab completes the command verb, then that command's flags
+pub struct CommandCompleter;
+
+impl Completer for CommandCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_line(&line[..pos]))
+    }
+}
+
+impl Hinter for CommandCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for CommandCompleter {}
+
+impl Validator for CommandCompleter {}
+
+impl Helper for CommandCompleter {}
+
+// Where the word under the cursor starts, and what it could be completed to.
+// The first word completes to a command; later words complete to flags of that
+// command, but only when they look like a flag (or are still empty).
+fn complete_line(line: &str) -> (usize, Vec<String>) {
+    let start = line.rfind([' ', '\t']).map_or(0, |i| i + 1);
+    let word = &line[start..];
+    let candidates = match line[..start].split_whitespace().next() {
+        None => COMMANDS,
+        Some(verb) if word.is_empty() || word.starts_with('-') => {
+            command_flags(&verb.to_lowercase())
+        }
+        Some(_) => &[],
+    };
+    let matches = candidates
+        .iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .map(|candidate| candidate.to_string())
+        .collect();
+    (start, matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_command() {
+        assert_eq!(complete_line("mon"), (0, vec!["monitor".to_string()]));
+        let (start, matches) = complete_line("kil");
+        assert_eq!(start, 0);
+        assert_eq!(matches, vec!["kill", "killuser", "killall", "killtree"]);
+        assert!(complete_line("xyz").1.is_empty());
+    }
+
+    #[test]
+    fn test_complete_flags() {
+        let (start, matches) = complete_line("ps ");
+        assert_eq!(start, 3);
+        assert!(matches.contains(&"-a".to_string()));
+        assert!(matches.contains(&"--all".to_string()));
+        assert!(matches.contains(&"-u".to_string()));
+        assert!(matches.contains(&"-s".to_string()));
+
+        assert_eq!(complete_line("info 42 --d"), (8, vec!["--detailed".to_string()]));
+        // A PID or a name isn't something to complete
+        assert!(complete_line("search fire").1.is_empty());
+        assert!(complete_line("mem ").1.is_empty());
+    }
+}
@@ -0,0 +1,39 @@
+use std::str::FromStr;
+
+use nix::sys::signal::Signal;
+
+// A signal given on the command line: a number (`9`), a full name (`SIGKILL`)
+// or a name without the prefix (`kill`). Case doesn't matter. None for anything
+// this platform doesn't define, so a typo is never sent as some other signal.
+pub fn parse_signal(s: &str) -> Option<Signal> {
+    if let Ok(number) = s.parse::<i32>() {
+        return Signal::try_from(number).ok();
+    }
+    let name = s.to_uppercase();
+    if name.starts_with("SIG") {
+        Signal::from_str(&name).ok()
+    } else {
+        Signal::from_str(&format!("SIG{}", name)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("SIGHUP"), Some(Signal::SIGHUP));
+        assert_eq!(parse_signal("hup"), Some(Signal::SIGHUP));
+        assert_eq!(parse_signal("KILL"), Some(Signal::SIGKILL));
+        assert_eq!(parse_signal("9"), Some(Signal::SIGKILL));
+        assert_eq!(parse_signal("SIGUSR2"), Some(Signal::SIGUSR2));
+        assert_eq!(parse_signal("cont"), Some(Signal::SIGCONT));
+
+        assert_eq!(parse_signal("SIGBOGUS"), None);
+        assert_eq!(parse_signal("0"), None);
+        assert_eq!(parse_signal("999"), None);
+        assert_eq!(parse_signal("-9"), None);
+        assert_eq!(parse_signal(""), None);
+    }
+}
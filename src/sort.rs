@@ -0,0 +1,263 @@
+use std::cmp::{Ordering, Reverse};
+
+use crate::proc_reader::ProcessMetrics;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Pss,
+    Score(Weights),
+    // Context switches; cumulative in `ps`, per second in `monitor`
+    CtxSw,
+    Usage(Resource),
+    Pid,
+}
+
+impl SortKey {
+    pub fn parse(key: &str) -> Option<SortKey> {
+        match key.to_lowercase().as_str() {
+            "pss" => Some(SortKey::Pss),
+            "score" => Some(SortKey::Score(Weights::default())),
+            "ctxsw" => Some(SortKey::CtxSw),
+            "pid" => Some(SortKey::Pid),
+            other => Resource::parse(other).map(SortKey::Usage),
+        }
+    }
+}
+
+// A resource that `top1` can rank processes by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resource {
+    Cpu,
+    Mem,
+    Io,
+}
+
+impl Resource {
+    pub fn parse(name: &str) -> Option<Resource> {
+        match name.to_lowercase().as_str() {
+            "cpu" => Some(Resource::Cpu),
+            "mem" | "memory" => Some(Resource::Mem),
+            "io" => Some(Resource::Io),
+            _ => None,
+        }
+    }
+}
+
+impl Resource {
+    // How much of the resource a process uses: %CPU, RSS in KB, or bytes read + written
+    pub fn usage(self, m: &ProcessMetrics) -> f64 {
+        match self {
+            Resource::Cpu => m.cpu_time,
+            Resource::Mem => m.mem_usage as f64,
+            Resource::Io => (m.io_read_bytes + m.io_write_bytes) as f64,
+        }
+    }
+}
+
+// The single heaviest consumer of a resource (lowest PID wins a tie)
+pub fn top_consumer(processes: &[ProcessMetrics], resource: Resource) -> Option<&ProcessMetrics> {
+    processes
+        .iter()
+        .reduce(|best, m| if resource.usage(m) > resource.usage(best) { m } else { best })
+}
+
+// Relative importance of CPU and memory in the combined score
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub cpu: f64,
+    pub mem: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights { cpu: 0.5, mem: 0.5 }
+    }
+}
+
+impl Weights {
+    // Parse `cpu=0.7,mem=0.3`; a dimension that isn't mentioned keeps its default
+    pub fn parse(spec: &str) -> Option<Weights> {
+        let mut weights = Weights::default();
+        for pair in spec.split(',') {
+            let (name, value) = pair.split_once('=')?;
+            let value: f64 = value.trim().parse().ok().filter(|v: &f64| *v >= 0.0)?;
+            match name.trim().to_lowercase().as_str() {
+                "cpu" => weights.cpu = value,
+                "mem" => weights.mem = value,
+                _ => return None,
+            }
+        }
+        Some(weights)
+    }
+}
+
+// Weighted combination of CPU and memory, each scaled against the largest value
+// in the set so that percentages and kilobytes are comparable
+pub fn scores(processes: &[ProcessMetrics], weights: Weights) -> Vec<f64> {
+    let max_cpu = processes.iter().map(|m| m.cpu_time).fold(0.0, f64::max);
+    let max_mem = processes.iter().map(|m| m.mem_usage).max().unwrap_or(0);
+
+    processes
+        .iter()
+        .map(|m| {
+            let cpu = if max_cpu > 0.0 { m.cpu_time / max_cpu } else { 0.0 };
+            let mem = if max_mem > 0 { m.mem_usage as f64 / max_mem as f64 } else { 0.0 };
+            weights.cpu * cpu + weights.mem * mem
+        })
+        .collect()
+}
+
+// Sort processes descending by the given key. Processes missing the value
+// (e.g. PSS that we weren't allowed to read) go to the end.
+pub fn sort_processes(processes: &mut [ProcessMetrics], key: SortKey) {
+    match key {
+        SortKey::Pss => processes.sort_by(|a, b| match (a.pss, b.pss) {
+            (Some(a), Some(b)) => b.cmp(&a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.pid.cmp(&b.pid),
+        }),
+        SortKey::CtxSw => processes.sort_by(|a, b| {
+            let switches = |m: &ProcessMetrics| m.voluntary_ctxt_switches + m.nonvoluntary_ctxt_switches;
+            switches(b).cmp(&switches(a)).then_with(|| a.pid.cmp(&b.pid))
+        }),
+        SortKey::Usage(resource) => processes.sort_by(|a, b| {
+            resource.usage(b).total_cmp(&resource.usage(a)).then_with(|| a.pid.cmp(&b.pid))
+        }),
+        SortKey::Pid => processes.sort_by_key(|m| Reverse(m.pid)),
+        SortKey::Score(weights) => {
+            let scores = scores(processes, weights);
+            let mut ranked: Vec<(f64, ProcessMetrics)> =
+                scores.into_iter().zip(processes.iter().cloned()).collect();
+            ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.pid.cmp(&b.1.pid)));
+            for (slot, (_, metrics)) in processes.iter_mut().zip(ranked) {
+                *slot = metrics;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_pss(pid: u32, pss: Option<u64>) -> ProcessMetrics {
+        ProcessMetrics {
+            pid,
+            pss,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sort_by_pss() {
+        let mut processes = vec![
+            with_pss(1, Some(100)),
+            with_pss(2, None),
+            with_pss(3, Some(5000)),
+            with_pss(4, Some(250)),
+        ];
+        sort_processes(&mut processes, SortKey::Pss);
+
+        let order: Vec<u32> = processes.iter().map(|m| m.pid).collect();
+        assert_eq!(order, vec![3, 4, 1, 2]);
+    }
+
+    fn with_usage(pid: u32, cpu_time: f64, mem_usage: u64) -> ProcessMetrics {
+        ProcessMetrics {
+            pid,
+            cpu_time,
+            mem_usage,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_weights() {
+        assert_eq!(Weights::parse("cpu=0.7,mem=0.3"), Some(Weights { cpu: 0.7, mem: 0.3 }));
+        assert_eq!(Weights::parse("mem=1"), Some(Weights { cpu: 0.5, mem: 1.0 }));
+        assert_eq!(Weights::parse("disk=1"), None);
+        assert_eq!(Weights::parse("cpu=-1"), None);
+        assert_eq!(Weights::parse("cpu"), None);
+    }
+
+    #[test]
+    fn test_sort_by_score() {
+        let processes = vec![
+            with_usage(1, 100.0, 1000), // CPU hog: cpu 1.0, mem 0.25
+            with_usage(2, 10.0, 4000),  // memory hog: cpu 0.1, mem 1.0
+            with_usage(3, 50.0, 2000),  // middling: cpu 0.5, mem 0.5
+        ];
+
+        let even = scores(&processes, Weights::default());
+        assert_eq!(even, vec![0.625, 0.55, 0.5]);
+
+        let mut ranked = processes.clone();
+        sort_processes(&mut ranked, SortKey::Score(Weights::default()));
+        let order: Vec<u32> = ranked.iter().map(|m| m.pid).collect();
+        assert_eq!(order, vec![1, 2, 3]);
+
+        let mut ranked = processes.clone();
+        sort_processes(&mut ranked, SortKey::Score(Weights { cpu: 0.2, mem: 0.8 }));
+        let order: Vec<u32> = ranked.iter().map(|m| m.pid).collect();
+        assert_eq!(order, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_sort_by_ctxsw() {
+        assert_eq!(SortKey::parse("CTXSW"), Some(SortKey::CtxSw));
+
+        let mut processes = vec![with_usage(1, 0.0, 0), with_usage(2, 0.0, 0), with_usage(3, 0.0, 0)];
+        processes[0].voluntary_ctxt_switches = 500;
+        processes[1].voluntary_ctxt_switches = 100;
+        processes[1].nonvoluntary_ctxt_switches = 900;
+        processes[2].nonvoluntary_ctxt_switches = 500;
+        sort_processes(&mut processes, SortKey::CtxSw);
+
+        let order: Vec<u32> = processes.iter().map(|m| m.pid).collect();
+        assert_eq!(order, vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn test_sort_by_usage() {
+        assert_eq!(SortKey::parse("cpu"), Some(SortKey::Usage(Resource::Cpu)));
+        assert_eq!(SortKey::parse("memory"), Some(SortKey::Usage(Resource::Mem)));
+        assert_eq!(SortKey::parse("disk"), None);
+
+        let mut processes = vec![
+            with_usage(1, 5.0, 9000),
+            with_usage(2, 80.0, 100),
+            with_usage(3, 40.0, 200),
+            with_usage(4, 80.0, 300),
+        ];
+        processes[0].io_write_bytes = 10;
+        processes[2].io_read_bytes = 4096;
+        let order = |processes: &[ProcessMetrics]| -> Vec<u32> { processes.iter().map(|m| m.pid).collect() };
+
+        // Ties keep PID order
+        sort_processes(&mut processes, SortKey::Usage(Resource::Cpu));
+        assert_eq!(order(&processes), vec![2, 4, 3, 1]);
+        sort_processes(&mut processes, SortKey::Usage(Resource::Mem));
+        assert_eq!(order(&processes), vec![1, 4, 3, 2]);
+        sort_processes(&mut processes, SortKey::Usage(Resource::Io));
+        assert_eq!(order(&processes), vec![3, 1, 2, 4]);
+        sort_processes(&mut processes, SortKey::Pid);
+        assert_eq!(order(&processes), vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_top_consumer() {
+        let mut processes = vec![
+            with_usage(1, 5.0, 9000),
+            with_usage(2, 80.0, 100),
+            with_usage(3, 80.0, 200),
+        ];
+        processes[0].io_read_bytes = 10;
+        processes[2].io_write_bytes = 4096;
+
+        assert_eq!(top_consumer(&processes, Resource::Cpu).map(|m| m.pid), Some(2));
+        assert_eq!(top_consumer(&processes, Resource::Mem).map(|m| m.pid), Some(1));
+        assert_eq!(top_consumer(&processes, Resource::Io).map(|m| m.pid), Some(3));
+        assert_eq!(top_consumer(&[], Resource::Cpu).map(|m| m.pid), None);
+    }
+}
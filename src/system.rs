@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+// Fields of /proc/meminfo, keyed by name without the trailing colon (values in KB)
+#[derive(Debug, Default)]
+pub struct MemInfo {
+    fields: HashMap<String, u64>,
+}
+
+impl MemInfo {
+    // Value of a field in KB, or 0 when this kernel doesn't report it
+    pub fn kb(&self, key: &str) -> u64 {
+        self.fields.get(key).copied().unwrap_or(0)
+    }
+
+    // Memory in use by processes and the kernel. MemAvailable (Linux 3.14+) also
+    // counts reclaimable slab and excludes unreclaimable page cache, so it's
+    // preferred over adding up free, buffers and cached, which older kernels need.
+    pub fn used_kb(&self) -> u64 {
+        let total = self.kb("MemTotal");
+        match self.fields.get("MemAvailable") {
+            Some(&available) => total.saturating_sub(available),
+            None => total.saturating_sub(self.kb("MemFree") + self.kb("Buffers") + self.kb("Cached")),
+        }
+    }
+
+    // Memory held by the kernel itself rather than any process. SReclaimable and
+    // SUnreclaim are the two halves of Slab, so they aren't added again.
+    pub fn kernel_kb(&self) -> u64 {
+        self.kb("Slab") + self.kb("KernelStack") + self.kb("PageTables")
+    }
+}
+
+// Fields shown by `kmem`, with the Slab breakdown indented under Slab
+pub const KERNEL_MEMORY_FIELDS: [(&str, &str); 5] = [
+    ("Slab", "Slab"),
+    ("SReclaimable", "  reclaimable"),
+    ("SUnreclaim", "  unreclaimable"),
+    ("KernelStack", "Kernel stacks"),
+    ("PageTables", "Page tables"),
+];
+
+pub fn parse_meminfo(content: &str) -> MemInfo {
+    let mut fields = HashMap::new();
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        if let (Some(key), Some(value)) = (parts.next(), parts.next())
+            && let Ok(value) = value.parse::<u64>()
+        {
+            fields.insert(key.trim_end_matches(':').to_string(), value);
+        }
+    }
+    MemInfo { fields }
+}
+
+pub fn read_meminfo() -> io::Result<MemInfo> {
+    Ok(parse_meminfo(&fs::read_to_string("/proc/meminfo")?))
+}
+
+// Highest PID the kernel hands out before wrapping around; PIDs and thread IDs
+// share this space
+pub fn read_pid_max() -> io::Result<u64> {
+    let content = fs::read_to_string("/proc/sys/kernel/pid_max")?;
+    content
+        .trim()
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "unreadable pid_max"))
+}
+
+// Total number of tasks (processes and threads), from the fourth field of
+// /proc/loadavg, which reads `running/total`
+pub fn parse_task_count(loadavg: &str) -> Option<u64> {
+    let (_, total) = loadavg.split_whitespace().nth(3)?.split_once('/')?;
+    total.parse().ok()
+}
+
+pub fn read_task_count() -> Option<u64> {
+    parse_task_count(&fs::read_to_string("/proc/loadavg").ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_meminfo() {
+        let info = parse_meminfo("MemTotal:       16000 kB\nMemFree:  4000 kB\nHugePages_Total:       0\n");
+        assert_eq!(info.kb("MemTotal"), 16000);
+        assert_eq!(info.kb("MemFree"), 4000);
+        assert_eq!(info.kb("HugePages_Total"), 0);
+        assert_eq!(info.kb("MemAvailable"), 0);
+    }
+
+    #[test]
+    fn test_used_memory() {
+        let info = parse_meminfo("MemTotal: 16000 kB\nMemFree: 4000 kB\nMemAvailable: 10000 kB\nCached: 5000 kB\n");
+        assert_eq!(info.used_kb(), 6000);
+
+        let old_kernel = parse_meminfo("MemTotal: 16000 kB\nMemFree: 4000 kB\nBuffers: 1000 kB\nCached: 5000 kB\n");
+        assert_eq!(old_kernel.used_kb(), 6000);
+
+        // Fields that add up to more than the total must not underflow
+        let odd = parse_meminfo("MemTotal: 1000 kB\nMemFree: 800 kB\nCached: 900 kB\n");
+        assert_eq!(odd.used_kb(), 0);
+    }
+
+    #[test]
+    fn test_parse_task_count() {
+        assert_eq!(parse_task_count("0.52 0.40 0.35 2/1043 12345\n"), Some(1043));
+        assert_eq!(parse_task_count("0.52 0.40 0.35"), None);
+    }
+
+    #[test]
+    fn test_kernel_memory() {
+        let info = parse_meminfo(
+            "Slab:             300000 kB\nSReclaimable:     200000 kB\nSUnreclaim:       100000 kB\n\
+             KernelStack:       16000 kB\nPageTables:        40000 kB\n",
+        );
+        assert_eq!(info.kb("SUnreclaim"), 100000);
+        assert_eq!(info.kernel_kb(), 356000);
+        assert_eq!(parse_meminfo("MemTotal: 1000 kB\n").kernel_kb(), 0);
+    }
+}
@@ -0,0 +1,39 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+use crossterm::terminal;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+}
+
+impl Color {
+    fn ansi_code(self) -> &'static str {
+        match self {
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Blue => "34",
+        }
+    }
+}
+
+// Colors only go to a terminal, and honour the NO_COLOR convention
+pub fn color_enabled() -> bool {
+    io::stdout().is_terminal()
+        && env::var_os("NO_COLOR").is_none()
+        && env::var("TERM").map(|t| t != "dumb").unwrap_or(true)
+}
+
+// Width of the terminal in columns, assuming 80 when it can't be queried
+pub fn terminal_width() -> usize {
+    terminal::size().map(|(cols, _)| cols as usize).unwrap_or(80)
+}
+
+pub fn paint(text: &str, color: Color) -> String {
+    format!("\x1B[{}m{}\x1B[0m", color.ansi_code(), text)
+}
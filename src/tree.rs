@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::proc_reader::ProcessMetrics;
+
+// The chain of (PID, name) from `pid` up through its parents, oldest first.
+// `lookup` gives a process's name and parent PID. The walk ends at PID 1 or
+// at a process whose parent is 0 (kernel threads, or the init of a PID
+// namespace we can't see past), and stops early at a process that can't be
+// read or, if the PID links ever loop, at one already visited.
+pub fn ancestry<F>(pid: u32, mut lookup: F) -> Vec<(u32, String)>
+where
+    F: FnMut(u32) -> Option<(String, u32)>,
+{
+    let mut chain = Vec::new();
+    let mut seen = HashSet::new();
+    let mut current = pid;
+    while current != 0 && seen.insert(current) {
+        let Some((comm, ppid)) = lookup(current) else {
+            break;
+        };
+        chain.push((current, comm));
+        if current == 1 {
+            break;
+        }
+        current = ppid;
+    }
+    chain.reverse();
+    chain
+}
+
+// "systemd(1) → sshd(812) → bash(1200)"
+pub fn format_ancestry(chain: &[(u32, String)]) -> String {
+    chain
+        .iter()
+        .map(|(pid, comm)| format!("{}({})", comm, pid))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+// Each PID's children, in PID order. A process whose parent isn't among
+// `processes` (PID 1, kernel threads, orphans of unreadable parents) hangs
+// off 0, the root of the whole tree.
+pub fn children_map(processes: &[ProcessMetrics]) -> HashMap<u32, Vec<u32>> {
+    let pids: HashSet<u32> = processes.iter().map(|metrics| metrics.pid).collect();
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for metrics in processes {
+        let parent = if metrics.ppid != metrics.pid && pids.contains(&metrics.ppid) {
+            metrics.ppid
+        } else {
+            0
+        };
+        children.entry(parent).or_default().push(metrics.pid);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_unstable();
+    }
+    children
+}
+
+// Reorder siblings at every level below `root`, heaviest `weight` first
+// (ties keep PID order). Only the order changes, never who is whose child.
+pub fn sort_children<F>(children: &mut HashMap<u32, Vec<u32>>, root: u32, weight: &F)
+where
+    F: Fn(u32) -> f64,
+{
+    sort_level(children, root, weight, &mut HashSet::new());
+}
+
+fn sort_level<F>(children: &mut HashMap<u32, Vec<u32>>, pid: u32, weight: &F, seen: &mut HashSet<u32>)
+where
+    F: Fn(u32) -> f64,
+{
+    if !seen.insert(pid) {
+        return;
+    }
+    let Some(siblings) = children.get_mut(&pid) else {
+        return;
+    };
+    siblings.sort_by(|a, b| weight(*b).total_cmp(&weight(*a)).then_with(|| a.cmp(b)));
+    for child in siblings.clone() {
+        sort_level(children, child, weight, seen);
+    }
+}
+
+// One line per process below `root`, depth first, indented two spaces per
+// level. A PID reached twice (only possible if the parent links loop) is
+// not descended into again.
+pub fn render<F>(children: &HashMap<u32, Vec<u32>>, root: u32, label: F) -> Vec<String>
+where
+    F: Fn(u32) -> String,
+{
+    let mut lines = Vec::new();
+    let mut seen = HashSet::from([root]);
+    let mut stack: Vec<(u32, usize)> = Vec::new();
+    let push_children = |stack: &mut Vec<(u32, usize)>, pid: u32, depth: usize| {
+        if let Some(siblings) = children.get(&pid) {
+            // Reversed so the first sibling is popped first
+            stack.extend(siblings.iter().rev().map(|&child| (child, depth)));
+        }
+    };
+    push_children(&mut stack, root, 0);
+    while let Some((pid, depth)) = stack.pop() {
+        if !seen.insert(pid) {
+            continue;
+        }
+        lines.push(format!("{}{}", "  ".repeat(depth), label(pid)));
+        push_children(&mut stack, pid, depth + 1);
+    }
+    lines
+}
+
+// Like `render`, but starting with `root` itself, its descendants indented under it
+pub fn render_subtree<F>(children: &HashMap<u32, Vec<u32>>, root: u32, label: F) -> Vec<String>
+where
+    F: Fn(u32) -> String,
+{
+    let mut lines = vec![label(root)];
+    lines.extend(render(children, root, &label).into_iter().map(|line| format!("  {}", line)));
+    lines
+}
+
+// `root` followed by everything below it, parents before their children
+pub fn descendants(children: &HashMap<u32, Vec<u32>>, root: u32) -> Vec<u32> {
+    let mut pids = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(pid) = stack.pop() {
+        if !seen.insert(pid) {
+            continue;
+        }
+        pids.push(pid);
+        if let Some(siblings) = children.get(&pid) {
+            stack.extend(siblings.iter().rev());
+        }
+    }
+    pids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    type Table = HashMap<u32, (&'static str, u32)>;
+
+    fn lookup(table: &Table) -> impl FnMut(u32) -> Option<(String, u32)> + '_ {
+        |pid| table.get(&pid).map(|&(comm, ppid)| (comm.to_string(), ppid))
+    }
+
+    #[test]
+    fn test_ancestry() {
+        let table: Table = [
+            (1, ("systemd", 0)),
+            (812, ("sshd", 1)),
+            (1200, ("bash", 812)),
+            (1300, ("vim", 1200)),
+            (2, ("kthreadd", 0)),
+        ]
+        .into_iter()
+        .collect();
+
+        let chain = ancestry(1300, lookup(&table));
+        assert_eq!(format_ancestry(&chain), "systemd(1) → sshd(812) → bash(1200) → vim(1300)");
+        assert_eq!(format_ancestry(&ancestry(2, lookup(&table))), "kthreadd(2)");
+        assert!(ancestry(4242, lookup(&table)).is_empty());
+    }
+
+    // systemd(1) → { sshd(812) → bash(1200), cron(900) }, plus kthreadd(2)
+    fn forest() -> Vec<ProcessMetrics> {
+        [(1, 0, 1.0), (2, 0, 0.0), (812, 1, 2.0), (900, 1, 30.0), (1200, 812, 50.0)]
+            .iter()
+            .map(|&(pid, ppid, cpu_time)| ProcessMetrics { pid, ppid, cpu_time, ..Default::default() })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let children = children_map(&forest());
+        assert_eq!(children[&0], vec![1, 2]);
+        assert_eq!(children[&1], vec![812, 900]);
+        assert_eq!(
+            render(&children, 0, |pid| pid.to_string()),
+            vec!["1", "  812", "    1200", "  900", "2"]
+        );
+    }
+
+    #[test]
+    fn test_render_subtree() {
+        let children = children_map(&forest());
+        assert_eq!(render_subtree(&children, 812, |pid| pid.to_string()), vec!["812", "  1200"]);
+        assert_eq!(render_subtree(&children, 1200, |pid| pid.to_string()), vec!["1200"]);
+
+        // A loop back to the root doesn't print the root again
+        let looped: HashMap<u32, Vec<u32>> = [(10, vec![20]), (20, vec![10])].into_iter().collect();
+        assert_eq!(render_subtree(&looped, 10, |pid| pid.to_string()), vec!["10", "  20"]);
+    }
+
+    #[test]
+    fn test_descendants() {
+        let children = children_map(&forest());
+        assert_eq!(descendants(&children, 1), vec![1, 812, 1200, 900]);
+        assert_eq!(descendants(&children, 1200), vec![1200]);
+
+        let looped: HashMap<u32, Vec<u32>> = [(10, vec![20]), (20, vec![10])].into_iter().collect();
+        assert_eq!(descendants(&looped, 10), vec![10, 20]);
+    }
+
+    #[test]
+    fn test_sort_children_keeps_hierarchy() {
+        let processes = forest();
+        let cpu: HashMap<u32, f64> = processes.iter().map(|m| (m.pid, m.cpu_time)).collect();
+        let mut children = children_map(&processes);
+        sort_children(&mut children, 0, &|pid| cpu[&pid]);
+
+        // cron (30%) now comes before sshd (2%), but bash still sits under sshd
+        assert_eq!(children[&1], vec![900, 812]);
+        assert_eq!(
+            render(&children, 0, |pid| pid.to_string()),
+            vec!["1", "  900", "  812", "    1200", "2"]
+        );
+    }
+
+    #[test]
+    fn test_render_survives_parent_loop() {
+        let children: HashMap<u32, Vec<u32>> = [(0, vec![10]), (10, vec![20]), (20, vec![10])].into_iter().collect();
+        assert_eq!(render(&children, 0, |pid| pid.to_string()), vec!["10", "  20"]);
+    }
+
+    #[test]
+    fn test_ancestry_stops_on_cycle() {
+        let table: Table =
+            [(10, ("a", 20)), (20, ("b", 30)), (30, ("c", 10))].into_iter().collect();
+        let pids: Vec<u32> = ancestry(10, lookup(&table)).iter().map(|(pid, _)| *pid).collect();
+        assert_eq!(pids, vec![30, 20, 10]);
+    }
+}
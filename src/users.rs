@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+
+// (name, UID) pairs from the lines of /etc/passwd (`name:password:uid:...`)
+fn parse_passwd(content: &str) -> Vec<(String, u32)> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((name.to_string(), uid))
+        })
+        .collect()
+}
+
+fn read_passwd() -> Vec<(String, u32)> {
+    fs::read_to_string("/etc/passwd")
+        .map(|content| parse_passwd(&content))
+        .unwrap_or_default()
+}
+
+// Resolve a user given by name or numeric UID
+pub fn resolve_uid(user: &str) -> Option<u32> {
+    if let Ok(uid) = user.parse() {
+        return Some(uid);
+    }
+    read_passwd()
+        .into_iter()
+        .find(|(name, _)| name == user)
+        .map(|(_, uid)| uid)
+}
+
+// UID to user name for every account in /etc/passwd, for labelling many processes
+pub fn user_names() -> HashMap<u32, String> {
+    read_passwd().into_iter().map(|(name, uid)| (uid, name)).collect()
+}
+
+// The owner name for a process row. The passwd table is read once and kept,
+// since a full scan resolves hundreds of processes owned by the same few users.
+pub fn owner_name(uid: u32) -> String {
+    static NAMES: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    display_name(NAMES.get_or_init(user_names), uid)
+}
+
+// A user's name, or the bare UID when it has no passwd entry (e.g. inside a container)
+pub fn display_name(names: &HashMap<u32, String>, uid: u32) -> String {
+    names.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_passwd() {
+        let content = "# comment\nroot:x:0:0:root:/root:/bin/bash\nwww-data:x:33:33::/var/www:/usr/sbin/nologin\nbroken\n";
+        assert_eq!(
+            parse_passwd(content),
+            vec![("root".to_string(), 0), ("www-data".to_string(), 33)]
+        );
+    }
+
+    #[test]
+    fn test_display_name() {
+        let names: HashMap<u32, String> = [(0, "root".to_string())].into_iter().collect();
+        assert_eq!(display_name(&names, 0), "root");
+        assert_eq!(display_name(&names, 1000), "1000");
+    }
+
+    #[test]
+    fn test_resolve_numeric_uid() {
+        assert_eq!(resolve_uid("1000"), Some(1000));
+    }
+}